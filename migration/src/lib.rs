@@ -1,10 +1,20 @@
 pub use sea_orm_migration::prelude::*;
 
+mod m20250101_000001_enable_pg_trgm;
+mod m20250101_000002_create_icons_table;
+mod m20250101_000003_create_svgs_table;
+mod m20250101_000004_enable_fuzzystrmatch;
+
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![]
+        vec![
+            Box::new(m20250101_000001_enable_pg_trgm::Migration),
+            Box::new(m20250101_000002_create_icons_table::Migration),
+            Box::new(m20250101_000003_create_svgs_table::Migration),
+            Box::new(m20250101_000004_enable_fuzzystrmatch::Migration),
+        ]
     }
 }
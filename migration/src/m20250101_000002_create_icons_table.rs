@@ -0,0 +1,72 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Databases created before migrations were bundled already have the table.
+        manager
+            .create_table(
+                Table::create()
+                    .table(Icons::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Icons::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Icons::Rid).text().not_null().unique_key())
+                    .col(ColumnDef::new(Icons::Name).text().not_null())
+                    .col(ColumnDef::new(Icons::Status).text().not_null())
+                    .col(ColumnDef::new(Icons::Category).text().not_null())
+                    .col(
+                        ColumnDef::new(Icons::SearchCategories)
+                            .array(ColumnType::Text)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Icons::Tags)
+                            .array(ColumnType::Text)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Icons::Notes).text())
+                    .col(ColumnDef::new(Icons::ReleasedAt).double())
+                    .col(ColumnDef::new(Icons::LastUpdatedAt).double())
+                    .col(ColumnDef::new(Icons::DeprecatedAt).double())
+                    .col(ColumnDef::new(Icons::Published).boolean().not_null())
+                    .col(ColumnDef::new(Icons::Alias).text())
+                    .col(ColumnDef::new(Icons::Code).integer().unique_key())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Icons::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Icons {
+    Table,
+    Id,
+    Rid,
+    Name,
+    Status,
+    Category,
+    SearchCategories,
+    Tags,
+    Notes,
+    ReleasedAt,
+    LastUpdatedAt,
+    DeprecatedAt,
+    Published,
+    Alias,
+    Code,
+}
@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20250101_000002_create_icons_table::Icons;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Svgs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Svgs::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Svgs::IconId).integer().not_null())
+                    .col(ColumnDef::new(Svgs::Weight).text().not_null())
+                    .col(ColumnDef::new(Svgs::Src).text().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("svgs_icon_id_fkey")
+                            .from(Svgs::Table, Svgs::IconId)
+                            .to(Icons::Table, Icons::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    // SVGs are upserted by icon and weight.
+                    .index(
+                        Index::create()
+                            .name("svgs_icon_id_weight_key")
+                            .col(Svgs::IconId)
+                            .col(Svgs::Weight)
+                            .unique(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Svgs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Svgs {
+    Table,
+    Id,
+    IconId,
+    Weight,
+    Src,
+}
@@ -0,0 +1,24 @@
+use sea_orm_migration::prelude::*;
+
+/// Levenshtein distance, used for typo-tolerant icon search.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("CREATE EXTENSION IF NOT EXISTS fuzzystrmatch")
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DROP EXTENSION IF EXISTS fuzzystrmatch")
+            .await?;
+        Ok(())
+    }
+}
@@ -1,109 +1,871 @@
-use crate::{db, icons, svgs, table};
-use tokio::fs;
+use crate::rate_limit::RateLimiter;
+use crate::renders::{PrimeJob, RenderCache};
+use crate::{config::Config, db, entities, icons, metrics, svgs, synonyms::Synonyms, table};
+use actix_web::web::Bytes;
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::{fs, sync::Mutex};
+use utoipa::ToSchema;
+
+/// A summary of the SVG files processed by an asset sync.
+#[derive(Debug, Default, Serialize)]
+pub struct AssetSyncReport {
+    /// SVGs stored in the database.
+    pub synced: usize,
+    /// Files rejected as malformed SVG.
+    pub invalid: usize,
+    /// Valid files that matched no icon in the database.
+    pub unmatched: usize,
+}
+
+/// A summary of the icons changed by a table sync.
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct SyncReport {
+    /// Icons that were not yet in the database.
+    pub inserted: usize,
+    /// Existing icons whose fields changed.
+    pub updated: usize,
+    /// Existing icons identical to the table row.
+    pub unchanged: usize,
+    /// Icons not compared because an incremental sync found them last updated before the newest
+    /// version in the database.
+    pub skipped: usize,
+    /// Names shared by several table rows. None of the rows sharing a name are upserted, since
+    /// icons are looked up by name.
+    pub conflicts: Vec<NameConflict>,
+}
+
+/// Table rows that map to the same icon name.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NameConflict {
+    #[schema(example = "cube")]
+    pub name: String,
+    /// The AppSheet row IDs sharing the name.
+    pub rids: Vec<String>,
+}
+
+/// Set aside the rows whose names are shared by another row, returning the remaining rows along
+/// with the conflicts.
+fn split_conflicts(icons: Vec<table::TableIcon>) -> (Vec<table::TableIcon>, Vec<NameConflict>) {
+    let conflicts = name_conflicts(&icons);
+    let conflicting = conflicts
+        .iter()
+        .map(|conflict| conflict.name.as_str())
+        .collect::<HashSet<_>>();
+    let icons = icons
+        .into_iter()
+        .filter(|icon| !conflicting.contains(icon.name.as_str()))
+        .collect();
+    (icons, conflicts)
+}
+
+/// Find names shared by more than one row, in name order.
+fn name_conflicts(icons: &[table::TableIcon]) -> Vec<NameConflict> {
+    let mut by_name = BTreeMap::<&str, Vec<String>>::new();
+    for icon in icons {
+        by_name
+            .entry(&icon.name)
+            .or_default()
+            .push(icon.rid.clone());
+    }
+    by_name
+        .into_iter()
+        .filter(|(_, rids)| rids.len() > 1)
+        .map(|(name, rids)| NameConflict {
+            name: name.to_string(),
+            rids,
+        })
+        .collect()
+}
+
+/// The body POSTed to `PHOSPHOR_SYNC_WEBHOOK_URL` after a sync.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum SyncEvent<'a> {
+    TableSync {
+        #[serde(flatten)]
+        report: &'a SyncReport,
+        version: Option<f64>,
+    },
+    AssetsSync {
+        #[serde(flatten)]
+        report: &'a AssetSyncReport,
+        version: Option<f64>,
+    },
+}
+
+/// What [`AppState::sync_asset`] did with a file.
+enum AssetOutcome {
+    Ready(entities::svgs::Model),
+    Invalid,
+    Unmatched,
+    Unreadable,
+}
+
+/// Holds a directory of SVGs for each weight.
+const ASSETS_DIR: &str = "./core/assets";
+/// Asset files are read this many at a time.
+const ASSET_SYNC_CONCURRENCY: usize = 16;
+/// SVGs are upserted in batches of this many, each in its own transaction.
+const SVG_BATCH_SIZE: usize = 500;
+
+/// Icons are rendered this many at a time while priming the render cache. PNG rendering is
+/// CPU-bound, so this is kept low to leave the blocking pool free for requests.
+const PRIME_CONCURRENCY: usize = 4;
+
+/// Webhook deliveries are abandoned after this long, so a dead endpoint is only ever logged.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
 
-#[derive(Debug)]
 pub struct AppState {
+    pub config: Config,
     pub db: db::Db,
+    /// The version of the AppSheet table as of the last successful sync. Held for the duration of
+    /// a sync, so it also serves as the lock preventing concurrent syncs.
+    pub table_version: Mutex<Option<table::TableVersion>>,
+    /// Every published icon in the default listing order, served for unfiltered `/v1/icons`
+    /// requests. Refreshed whenever the catalog is written; `None` until first loaded.
+    icon_cache: RwLock<Option<Arc<Vec<entities::icons::Model>>>>,
+    /// Search synonyms, reloaded from `PHOSPHOR_SYNONYMS_PATH` on each admin sync.
+    synonyms: RwLock<Arc<Synonyms>>,
+    /// The serialized `/v1/filters` response, which only changes with the catalog's tags.
+    /// Cleared whenever the catalog is written; `None` until first requested.
+    filters: RwLock<Option<Bytes>>,
+    /// SVG sources and PNG renders served by the raw asset endpoints. Cleared whenever the catalog
+    /// or its assets are written.
+    pub renders: RenderCache,
+    /// The most recent cache-priming job, kept after it completes so its outcome can be read.
+    prime_job: RwLock<Option<Arc<PrimeJob>>>,
+    /// Per-client request budgets, enforced by the rate limiting middleware.
+    pub rate_limiter: RateLimiter,
+}
+
+/// Leaves out the caches, which hold the whole catalog and its renders, so that the state is cheap
+/// to log.
+impl std::fmt::Debug for AppState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppState")
+            .field("config", &self.config)
+            .field("db", &self.db)
+            .field("renders", &self.renders)
+            .field("rate_limiter", &self.rate_limiter)
+            .finish_non_exhaustive()
+    }
 }
 
 impl AppState {
-    #[tracing::instrument(level = "info")]
-    pub async fn init() -> Result<Self, std::io::Error> {
-        let db = db::Db::init().await.map_err(|_| {
-            tracing::error!("Failed to initialize database");
-            std::io::Error::new(std::io::ErrorKind::Other, "Failed to initialize database")
+    /// State around an existing database connection, with empty caches and nothing synced. Use
+    /// [`AppState::init`] to connect, check the schema, and run the configured syncs.
+    pub fn new(config: Config, db: db::Db, synonyms: Synonyms) -> Self {
+        AppState {
+            config,
+            db,
+            table_version: Mutex::new(None),
+            icon_cache: RwLock::new(None),
+            synonyms: RwLock::new(Arc::new(synonyms)),
+            filters: RwLock::new(None),
+            renders: RenderCache::default(),
+            prime_job: RwLock::new(None),
+            rate_limiter: RateLimiter::default(),
+        }
+    }
+
+    #[tracing::instrument(level = "info", skip_all)]
+    pub async fn init(config: Config) -> Result<Self, std::io::Error> {
+        let db = db::Db::init(&config).await.map_err(|e| {
+            tracing::error!("Failed to initialize database: {e}");
+            std::io::Error::other(format!("Failed to initialize database: {e}"))
+        })?;
+
+        let missing = db.missing_tables().await.map_err(|e| {
+            tracing::error!("Failed to check database schema: {e}");
+            std::io::Error::other(format!("Failed to check database schema: {e}"))
+        })?;
+        if !missing.is_empty() {
+            let msg = format!(
+                "Database is missing the {} table(s); run the migrations with \
+                 `phosphor-server migrate` (or `cargo run -- migrate`) before starting the server",
+                missing.join(", ")
+            );
+            tracing::error!("{msg}");
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, msg));
+        }
+
+        let synonyms = Synonyms::load(&config.synonyms_path).await.map_err(|e| {
+            tracing::error!("{e}");
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
         })?;
+        tracing::info!("Loaded synonyms for {} terms", synonyms.len());
 
-        let mut app = AppState { db };
+        let app = AppState::new(config, db, synonyms);
 
-        if let Ok(val) = std::env::var("PHOSPHOR_TABLE_SYNC") {
-            tracing::info!("PHOSPHOR_TABLE_SYNC={}", val);
-            if val == "true" {
-                app.sync_table().await?;
+        tracing::info!("PHOSPHOR_TABLE_SYNC={}", app.config.sync.table);
+        if app.config.sync.table {
+            app.sync_table().await?;
+        }
+
+        tracing::info!("PHOSPHOR_ASSETS_SYNC={}", app.config.sync.assets);
+        if app.config.sync.assets {
+            app.sync_assets().await?;
+        }
+
+        if app.icon_cache.read().is_ok_and(|cache| cache.is_none()) {
+            app.refresh_icon_cache().await;
+        }
+
+        Ok(app)
+    }
+
+    pub fn cached_icons(&self) -> Option<Arc<Vec<entities::icons::Model>>> {
+        let icons = self.icon_cache.read().ok().and_then(|cache| cache.clone());
+        metrics::observe_cache("icons", icons.is_some());
+        icons
+    }
+
+    /// Reload the icon cache from the database. On failure the cache is cleared, so that requests
+    /// fall back to querying the database rather than serving stale icons.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn refresh_icon_cache(&self) {
+        let icons = match self.db.get_icons(&db::IconQuery::new()).await {
+            Ok(icons) => {
+                tracing::info!("Cached {} icons", icons.len());
+                metrics::set_icon_cache_size(icons.len());
+                Some(Arc::new(icons))
+            }
+            Err(e) => {
+                tracing::error!("Failed to refresh icon cache: {e}");
+                metrics::set_icon_cache_size(0);
+                None
             }
+        };
+        if let Ok(mut cache) = self.icon_cache.write() {
+            if let Some(evicted) = std::mem::replace(&mut *cache, icons) {
+                metrics::record_cache_evictions("icons", evicted.len());
+            }
+        }
+        if let Ok(mut filters) = self.filters.write() {
+            *filters = None;
+        }
+        self.renders.clear();
+    }
+
+    pub fn cached_filters(&self) -> Option<Bytes> {
+        let filters = self.filters.read().ok().and_then(|cache| cache.clone());
+        metrics::observe_cache("filters", filters.is_some());
+        filters
+    }
+
+    /// Hold the serialized `/v1/filters` response until the catalog next changes.
+    pub fn cache_filters(&self, json: Bytes) {
+        if let Ok(mut filters) = self.filters.write() {
+            *filters = Some(json);
         }
+    }
 
-        if let Ok(val) = std::env::var("PHOSPHOR_ASSETS_SYNC") {
-            tracing::info!("PHOSPHOR_ASSETS_SYNC={}", val);
-            if val == "true" {
-                app.sync_assets().await?;
+    /// The source of a single weight of an icon, from the render cache when present. Returns
+    /// `None` if the icon has no SVG in that weight.
+    pub async fn svg_source(
+        &self,
+        icon_id: i32,
+        weight: &icons::IconWeight,
+    ) -> Result<Option<Arc<str>>, sea_orm::DbErr> {
+        if let Some(src) = self.renders.svg(icon_id, weight) {
+            return Ok(Some(src));
+        }
+        let Some(svg) = self
+            .db
+            .get_svg_weights_by_icon_id(icon_id)
+            .await?
+            .remove(weight)
+        else {
+            return Ok(None);
+        };
+        let src = Arc::<str>::from(svg.src);
+        self.renders.insert_svg(icon_id, weight, src.clone());
+        Ok(Some(src))
+    }
+
+    /// The first weight of an icon's [`icons::IconWeight::fallback_chain`] that has an SVG, or
+    /// `None` if the icon has none. The chosen SVG is cached, ready to be served.
+    pub async fn fallback_weight(
+        &self,
+        icon_id: i32,
+        weight: &icons::IconWeight,
+    ) -> Result<Option<icons::IconWeight>, sea_orm::DbErr> {
+        if self.renders.svg(icon_id, weight).is_some() {
+            return Ok(Some(weight.clone()));
+        }
+        let mut svgmap = self.db.get_svg_weights_by_icon_id(icon_id).await?;
+        let Some(served) = weight
+            .fallback_chain()
+            .into_iter()
+            .find(|weight| svgmap.contains_key(weight))
+        else {
+            return Ok(None);
+        };
+        if let Some(svg) = svgmap.remove(&served) {
+            self.renders
+                .insert_svg(icon_id, &served, Arc::from(svg.src));
+        }
+        Ok(Some(served))
+    }
+
+    /// A single weight of an icon rendered to a square PNG, from the render cache when present.
+    /// Returns `None` if the icon has no SVG in that weight.
+    pub async fn png(
+        &self,
+        icon_id: i32,
+        weight: &icons::IconWeight,
+        size: u32,
+    ) -> Result<Option<Bytes>, std::io::Error> {
+        if let Some(png) = self.renders.png(icon_id, weight, size) {
+            return Ok(Some(png));
+        }
+        let Some(src) = self
+            .svg_source(icon_id, weight)
+            .await
+            .map_err(|e| std::io::Error::other(format!("Failed to load SVG: {e}")))?
+        else {
+            return Ok(None);
+        };
+        let png = rasterize(src, size).await?;
+        self.renders.insert_png(icon_id, weight, size, png.clone());
+        Ok(Some(png))
+    }
+
+    /// The most recent cache-priming job, if it has the given ID.
+    pub fn prime_job(&self, id: uuid::Uuid) -> Option<Arc<PrimeJob>> {
+        self.prime_job
+            .read()
+            .ok()
+            .and_then(|job| job.clone())
+            .filter(|job| job.id == id)
+    }
+
+    /// Start rendering every stored weight of every published icon into the render cache in the
+    /// background, returning the job tracking its progress. Fails with
+    /// [`std::io::ErrorKind::WouldBlock`] if a priming job is already running.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub fn prime_renders(
+        self: Arc<Self>,
+        formats: Vec<db::RenderFormat>,
+        size: u32,
+    ) -> Result<Arc<PrimeJob>, std::io::Error> {
+        let job = {
+            let mut current = self
+                .prime_job
+                .write()
+                .map_err(|_| std::io::Error::other("Cache priming state is poisoned"))?;
+            if current.as_ref().is_some_and(|job| !job.is_done()) {
+                tracing::warn!("Cache priming already in progress");
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::WouldBlock,
+                    "Cache priming already in progress",
+                ));
             }
+            let job = Arc::new(PrimeJob::new());
+            *current = Some(job.clone());
+            job
+        };
+
+        let running = job.clone();
+        tokio::spawn(async move {
+            self.run_prime(&running, &formats, size).await;
+            running.finish();
+        });
+        Ok(job)
+    }
+
+    async fn run_prime(&self, job: &PrimeJob, formats: &[db::RenderFormat], size: u32) {
+        let icons = match self.cached_icons() {
+            Some(icons) => icons,
+            None => match self.db.get_icons(&db::IconQuery::new()).await {
+                Ok(icons) => Arc::new(icons),
+                Err(e) => {
+                    tracing::error!("Failed to load icons to prime: {e}");
+                    return;
+                }
+            },
+        };
+        let ids = icons.iter().map(|model| model.id).collect::<Vec<_>>();
+        let svgs = match self.db.get_svg_weights_by_icon_ids(&ids).await {
+            Ok(svgs) => svgs,
+            Err(e) => {
+                tracing::error!("Failed to load SVGs to prime: {e}");
+                return;
+            }
+        };
+
+        let renders = svgs
+            .into_iter()
+            .flat_map(|(icon_id, weights)| {
+                weights.into_iter().flat_map(move |(weight, svg)| {
+                    let src = Arc::<str>::from(svg.src);
+                    formats
+                        .iter()
+                        .map(move |format| (icon_id, weight.clone(), *format, src.clone()))
+                })
+            })
+            .collect::<Vec<_>>();
+        job.set_total(renders.len());
+        tracing::info!("Priming {} renders", renders.len());
+
+        let started = Instant::now();
+        stream::iter(renders)
+            .map(|(icon_id, weight, format, src)| async move {
+                match format {
+                    db::RenderFormat::Svg => {
+                        self.renders.insert_svg(icon_id, &weight, src);
+                        true
+                    }
+                    db::RenderFormat::Png => match rasterize(src, size).await {
+                        Ok(png) => {
+                            self.renders.insert_png(icon_id, &weight, size, png);
+                            true
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to render {icon_id} - {weight}: {e}");
+                            false
+                        }
+                    },
+                }
+            })
+            .buffer_unordered(PRIME_CONCURRENCY)
+            .for_each(|ok| async move { job.record(ok) })
+            .await;
+
+        let status = job.status();
+        tracing::info!(
+            "Primed render cache in {:?}: {} rendered, {} failed",
+            started.elapsed(),
+            status.rendered,
+            status.failed
+        );
+    }
+
+    pub fn synonyms(&self) -> Arc<Synonyms> {
+        self.synonyms
+            .read()
+            .map(|synonyms| synonyms.clone())
+            .unwrap_or_default()
+    }
+
+    /// Re-read the synonym file, returning the number of terms loaded. On failure the current
+    /// synonyms are kept.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn reload_synonyms(&self) -> Result<usize, std::io::Error> {
+        let synonyms = Synonyms::load(&self.config.synonyms_path)
+            .await
+            .map_err(|e| {
+                tracing::error!("{e}");
+                std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
+            })?;
+        let count = synonyms.len();
+        if let Ok(mut current) = self.synonyms.write() {
+            *current = Arc::new(synonyms);
         }
+        tracing::info!("Reloaded synonyms for {count} terms");
+        Ok(count)
+    }
 
-        Ok(app)
+    /// Search published icons, expanding the term with any configured synonyms. Returns a page of
+    /// results along with the total number of matches.
+    pub async fn search(
+        &self,
+        query: &db::IconSearch,
+    ) -> Result<(Vec<db::ScoredModel>, u64), sea_orm::DbErr> {
+        let synonyms = self.synonyms().expand(&query.q);
+        self.db.query_icons(query, &synonyms).await
     }
 
-    #[tracing::instrument(level = "info")]
-    async fn sync_table(&mut self) -> Result<(), std::io::Error> {
+    /// Pulls the AppSheet table and upserts every icon, returning what changed, or `None` if the
+    /// table is unchanged since the last sync. With `PHOSPHOR_INCREMENTAL_SYNC`, only icons
+    /// updated in or after the newest version in the database are upserted. Fails with
+    /// [`std::io::ErrorKind::WouldBlock`] if another sync is already in progress.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn sync_table(&self) -> Result<Option<SyncReport>, std::io::Error> {
+        let mut table_version = self.table_version.try_lock().map_err(|_| {
+            tracing::warn!("Table sync already in progress");
+            std::io::Error::new(
+                std::io::ErrorKind::WouldBlock,
+                "Table sync already in progress",
+            )
+        })?;
         tracing::info!("Syncing table client");
 
-        let icons = table::TableClient::sync().await.map_err(|_| {
-            tracing::error!("Failed to sync table client");
-            std::io::Error::new(std::io::ErrorKind::Other, "Failed to sync table client")
+        let since = if self.config.sync.incremental {
+            self.db.get_last_updated_version().await.map_err(|e| {
+                tracing::error!("Failed to read last updated version: {e}");
+                std::io::Error::other(format!("Failed to read last updated version: {e}"))
+            })?
+        } else {
+            None
+        };
+
+        let appsheet = &self.config.appsheet;
+        let res = match since {
+            Some(since) => {
+                table::TableClient::sync_since(appsheet, table_version.as_ref(), since).await
+            }
+            None => table::TableClient::sync_if_changed(appsheet, table_version.as_ref())
+                .await
+                .map(|res| res.map(|(icons, version)| (icons, 0, version))),
+        }
+        .map_err(|e| {
+            tracing::error!("Failed to sync table client: {e}");
+            std::io::Error::other(format!("Failed to sync table client: {e}"))
         })?;
+        let Some((icons, skipped, version)) = res else {
+            tracing::info!("Table unchanged since last sync, skipping");
+            return Ok(None);
+        };
+        if let Some(since) = since {
+            tracing::info!("Skipped {skipped} icons unchanged since version {since}");
+        }
+
+        let (icons, conflicts) = split_conflicts(icons);
+        for conflict in &conflicts {
+            tracing::warn!(
+                "Icon name {:?} is shared by rows {}",
+                conflict.name,
+                conflict.rids.join(", ")
+            );
+        }
+        if !conflicts.is_empty() && self.config.sync.strict {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} icon names are shared by several rows", conflicts.len()),
+            ));
+        }
+
+        let mut report = SyncReport {
+            skipped,
+            conflicts,
+            ..SyncReport::default()
+        };
         for icon in icons {
-            self.db
+            let outcome = self
+                .db
                 .upsert_icon(icon.clone().into())
                 .await
                 .map_err(|e| {
                     tracing::error!("Failed to upsert icon: {:?}: {:?}", &icon, e);
-                    std::io::Error::new(std::io::ErrorKind::Other, "Failed to upsert icon")
+                    std::io::Error::other("Failed to upsert icon")
                 })?;
+            match outcome {
+                db::UpsertOutcome::Inserted => report.inserted += 1,
+                db::UpsertOutcome::Updated => report.updated += 1,
+                db::UpsertOutcome::Unchanged => report.unchanged += 1,
+            }
         }
 
-        Ok(())
+        tracing::info!(
+            "Synced table: {} inserted, {} updated, {} unchanged, {} skipped, {} name conflicts",
+            report.inserted,
+            report.updated,
+            report.unchanged,
+            report.skipped,
+            report.conflicts.len()
+        );
+        *table_version = Some(version);
+        self.refresh_icon_cache().await;
+        let version = self.library_version().await;
+        self.notify_sync(&SyncEvent::TableSync {
+            report: &report,
+            version,
+        });
+        Ok(Some(report))
     }
 
-    #[tracing::instrument(level = "info")]
-    async fn sync_assets(&self) -> Result<(), std::io::Error> {
-        const ASSETS_DIR: &str = "./core/assets";
+    #[tracing::instrument(level = "info", skip(self))]
+    async fn sync_assets(&self) -> Result<AssetSyncReport, std::io::Error> {
         tracing::info!("Syncing assets");
+        let files = asset_files(Path::new(ASSETS_DIR)).await?;
+
+        let ids = self.db.get_icon_ids_by_name().await.map_err(|e| {
+            tracing::error!("Failed to load icon names: {:?}", e);
+            std::io::Error::other("Failed to load icon names")
+        })?;
 
-        let mut files: Vec<(String, icons::IconWeight)> = Vec::new();
+        let started = Instant::now();
+        let mut outcomes = stream::iter(files)
+            .map(|(path, weight)| self.sync_asset(path, weight, &ids))
+            .buffer_unordered(ASSET_SYNC_CONCURRENCY);
 
-        for weight in icons::IconWeight::ALL {
-            let path = format!("{}/{}", ASSETS_DIR, weight.to_string());
-            let mut dir = fs::read_dir(&path).await?;
-
-            while let Some(entry) = dir.next_entry().await? {
-                if entry.file_type().await?.is_file() {
-                    let file_name = entry.file_name().to_str().unwrap().to_owned();
-                    if file_name.ends_with(".svg") {
-                        let path = format!("{}/{}", path, file_name);
-                        files.push((path, weight.clone()));
-                    }
-                }
+        let mut report = AssetSyncReport::default();
+        let mut batch = Vec::with_capacity(SVG_BATCH_SIZE);
+        while let Some(outcome) = outcomes.next().await {
+            match outcome {
+                AssetOutcome::Ready(svg) => batch.push(svg),
+                AssetOutcome::Invalid => report.invalid += 1,
+                AssetOutcome::Unmatched => report.unmatched += 1,
+                AssetOutcome::Unreadable => {}
+            }
+            if batch.len() >= SVG_BATCH_SIZE {
+                report.synced += self.store_svgs(std::mem::take(&mut batch)).await?;
             }
         }
+        report.synced += self.store_svgs(batch).await?;
+        self.renders.clear();
 
-        for (path, weight) in files {
-            if let Ok(contents) = fs::read_to_string(&path).await {
-                let name = path
-                    .split('/')
-                    .last()
-                    .unwrap()
-                    .replace("-duotone.svg", "")
-                    .replace("-fill.svg", "")
-                    .replace("-thin.svg", "")
-                    .replace("-light.svg", "")
-                    .replace("-bold.svg", "")
-                    .replace(".svg", "")
-                    .to_string();
-                if let Some(icon) = self.db.get_icon_by_name(&name).await.unwrap() {
-                    let svg = svgs::Svg {
-                        id: 0,
-                        icon_id: icon.id,
-                        weight: weight.clone(),
-                        src: contents,
-                    };
-                    self.db.upsert_svg(svg.clone().into()).await.unwrap();
-                    tracing::info!("Upserted SVG: {} - {:?}", name, weight);
-                } else {
-                    tracing::warn!("Icon not found in database: {}", name);
-                }
+        tracing::info!(
+            "Synced assets in {:?}: {} stored, {} malformed, {} unmatched",
+            started.elapsed(),
+            report.synced,
+            report.invalid,
+            report.unmatched
+        );
+        let version = self.library_version().await;
+        self.notify_sync(&SyncEvent::AssetsSync {
+            report: &report,
+            version,
+        });
+        Ok(report)
+    }
+
+    /// Read and clean up a single asset file, ready to be stored.
+    async fn sync_asset(
+        &self,
+        path: String,
+        weight: icons::IconWeight,
+        ids: &HashMap<String, i32>,
+    ) -> AssetOutcome {
+        let mut contents = match fs::read_to_string(&path).await {
+            Ok(contents) => contents,
+            Err(e) => {
+                tracing::warn!("Failed to read asset {}: {}", path, e);
+                return AssetOutcome::Unreadable;
+            }
+        };
+        if let Err(e) = svgs::validate(&contents) {
+            tracing::error!("Skipping malformed SVG {}: {}", path, e);
+            return AssetOutcome::Invalid;
+        }
+        if self.config.sync.optimize_svg {
+            contents = svgs::optimize(&contents);
+        }
+        if self.config.sync.normalize_color {
+            if let Some(normalized) = svgs::normalize_color(&contents) {
+                tracing::warn!("Normalized hardcoded colors to currentColor: {}", path);
+                contents = normalized;
             }
         }
+        let file_name = path.split('/').next_back().unwrap_or_default();
+        let Some(name) = svgs::parse_icon_name_for_weight(file_name, &weight) else {
+            tracing::warn!("Unrecognized asset file name: {}", path);
+            return AssetOutcome::Unmatched;
+        };
+        let Some(&icon_id) = ids.get(&name) else {
+            tracing::warn!("Icon not found in database: {}", name);
+            return AssetOutcome::Unmatched;
+        };
+
+        AssetOutcome::Ready(
+            svgs::Svg {
+                id: 0,
+                icon_id,
+                weight,
+                src: contents,
+            }
+            .into(),
+        )
+    }
+
+    /// Upsert a batch of SVGs, returning how many were stored.
+    async fn store_svgs(&self, batch: Vec<entities::svgs::Model>) -> Result<usize, std::io::Error> {
+        let count = batch.len();
+        if count == 0 {
+            return Ok(0);
+        }
+        self.db.upsert_svgs(batch).await.map_err(|e| {
+            tracing::error!("Failed to upsert {} SVGs: {:?}", count, e);
+            std::io::Error::other("Failed to upsert SVGs")
+        })?;
+        tracing::info!("Upserted {} SVGs", count);
+        Ok(count)
+    }
+
+    async fn library_version(&self) -> Option<f64> {
+        self.db
+            .get_library_info()
+            .await
+            .map(|info| info.version)
+            .inspect_err(|e| tracing::warn!("Failed to read library version: {e}"))
+            .ok()
+    }
+
+    /// POST a sync event to `PHOSPHOR_SYNC_WEBHOOK_URL`, if set. Delivery happens in the
+    /// background and its outcome is only logged, so a slow or dead endpoint never holds up a
+    /// sync.
+    fn notify_sync(&self, event: &SyncEvent) {
+        let Some(url) = self
+            .config
+            .sync
+            .webhook_url
+            .as_ref()
+            .map(|url| url.expose().to_owned())
+        else {
+            return;
+        };
+        let body = match serde_json::to_value(event) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::error!("Failed to serialize sync webhook: {e}");
+                return;
+            }
+        };
+
+        // The URL may carry a secret in its path or query, so only its host is logged.
+        let host = reqwest::Url::parse(&url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_owned))
+            .unwrap_or_else(|| "<invalid URL>".to_string());
+        tokio::spawn(async move {
+            let res = reqwest::Client::new()
+                .post(&url)
+                .timeout(WEBHOOK_TIMEOUT)
+                .json(&body)
+                .send()
+                .await
+                .and_then(|res| res.error_for_status());
+            match res {
+                Ok(res) => tracing::info!("Delivered sync webhook to {host}: {}", res.status()),
+                Err(e) => tracing::warn!(
+                    "Failed to deliver sync webhook to {host}: {}",
+                    e.without_url()
+                ),
+            }
+        });
+    }
+}
+
+/// List the SVG files under `root`, which holds a directory of assets for each weight, along with
+/// the weight of the directory each was found in.
+async fn asset_files(root: &Path) -> Result<Vec<(String, icons::IconWeight)>, std::io::Error> {
+    let mut files = Vec::new();
+    for weight in icons::IconWeight::ALL {
+        let path = root.join(weight.to_string());
+        let mut dir = fs::read_dir(&path).await?;
+
+        while let Some(entry) = dir.next_entry().await? {
+            if !entry.file_type().await?.is_file() {
+                continue;
+            }
+            let file_name = entry.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                tracing::warn!("Skipping asset with a non-UTF-8 name: {:?}", entry.path());
+                continue;
+            };
+            if file_name.ends_with(".svg") {
+                files.push((format!("{}/{}", path.display(), file_name), weight.clone()));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Render an SVG to a square PNG on the blocking pool.
+async fn rasterize(src: Arc<str>, size: u32) -> Result<Bytes, std::io::Error> {
+    tokio::task::spawn_blocking(move || svgs::render_png(&src, size))
+        .await
+        .map_err(std::io::Error::other)?
+        .map(Bytes::from)
+        .map_err(std::io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(rid: &str, name: &str) -> table::TableIcon {
+        table::TableIcon {
+            rid: rid.to_string(),
+            name: name.to_string(),
+            ..table::TableIcon::default()
+        }
+    }
+
+    #[test]
+    fn rows_sharing_a_name_are_set_aside() {
+        let (icons, conflicts) = split_conflicts(vec![
+            row("r1", "cube"),
+            row("r2", "sphere"),
+            row("r3", "cube"),
+            row("r4", "cone"),
+        ]);
+        let rids = icons
+            .iter()
+            .map(|icon| icon.rid.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(rids, ["r2", "r4"]);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].name, "cube");
+        assert_eq!(conflicts[0].rids, ["r1", "r3"]);
+    }
+
+    /// The file name of the `cube` asset in `weight`, as published.
+    fn cube_file(weight: &icons::IconWeight) -> String {
+        match weight {
+            icons::IconWeight::Regular => "cube.svg".to_string(),
+            _ => format!("cube-{weight}.svg"),
+        }
+    }
+
+    #[tokio::test]
+    async fn asset_files_take_their_directory_weight() {
+        let root = std::env::temp_dir().join(format!("phosphor-assets-{}", uuid::Uuid::new_v4()));
+        for weight in icons::IconWeight::ALL {
+            let dir = root.join(weight.to_string());
+            std::fs::create_dir_all(dir.join("nested")).unwrap();
+            std::fs::write(dir.join(cube_file(&weight)), "<svg/>").unwrap();
+            std::fs::write(dir.join("nested/ignored.svg"), "<svg/>").unwrap();
+        }
+        // Misfiled, and not an SVG.
+        std::fs::write(root.join("regular/sphere-bold.svg"), "<svg/>").unwrap();
+        std::fs::write(root.join("bold/README.md"), "").unwrap();
+
+        let files = asset_files(&root).await;
+        std::fs::remove_dir_all(&root).unwrap();
+        let mut files = files
+            .unwrap()
+            .into_iter()
+            .map(|(path, weight)| {
+                let file_name = path.rsplit('/').next().unwrap().to_owned();
+                let name = svgs::parse_icon_name_for_weight(&file_name, &weight);
+                (weight, file_name, name)
+            })
+            .collect::<Vec<_>>();
+        files.sort_by_key(|(weight, file_name, _)| (weight.to_string(), file_name.clone()));
+
+        let mut expected = icons::IconWeight::ALL
+            .into_iter()
+            .map(|weight| (weight.clone(), cube_file(&weight), Some("cube".to_string())))
+            .collect::<Vec<_>>();
+        expected.push((
+            icons::IconWeight::Regular,
+            "sphere-bold.svg".to_string(),
+            Some("sphere-bold".to_string()),
+        ));
+        expected.sort_by_key(|(weight, file_name, _)| (weight.to_string(), file_name.clone()));
+        assert_eq!(files, expected);
+    }
+
+    #[tokio::test]
+    async fn asset_files_require_every_weight_directory() {
+        let root = std::env::temp_dir().join(format!("phosphor-assets-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("regular")).unwrap();
+        let files = asset_files(&root).await;
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(files.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+    }
 
-        Ok(())
+    #[test]
+    fn unique_names_have_no_conflicts() {
+        let (icons, conflicts) = split_conflicts(vec![row("r1", "cube"), row("r2", "sphere")]);
+        assert_eq!(icons.len(), 2);
+        assert!(conflicts.is_empty());
     }
 }
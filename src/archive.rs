@@ -0,0 +1,50 @@
+use actix_web::web::Bytes;
+use std::io::{self, Write};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+/// How many chunks may be queued ahead of the client before the archive writer blocks.
+const CHANNEL_CAPACITY: usize = 16;
+
+/// A [`Write`] sink that forwards each chunk to an async channel, so that an archive built on a
+/// blocking thread can be streamed to the client as it is written.
+struct ChannelWriter(mpsc::Sender<Result<Bytes, io::Error>>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Client disconnected"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Stream a ZIP archive of `(file name, contents)` pairs. The archive is compressed on a blocking
+/// thread and never held in memory as a whole; if the client disconnects, writing stops.
+pub fn stream_zip(files: Vec<(String, String)>) -> ReceiverStream<Result<Bytes, io::Error>> {
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+    let errors = tx.clone();
+    tokio::task::spawn_blocking(move || {
+        let res = (|| -> zip::result::ZipResult<()> {
+            let mut zip = ZipWriter::new_stream(ChannelWriter(tx));
+            let options =
+                SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+            for (name, contents) in files {
+                zip.start_file(name, options)?;
+                zip.write_all(contents.as_bytes())?;
+            }
+            zip.finish()?;
+            Ok(())
+        })();
+        if let Err(e) = res {
+            tracing::error!("Failed to write ZIP archive: {e}");
+            let _ = errors.blocking_send(Err(io::Error::other(e)));
+        }
+    });
+    ReceiverStream::new(rx)
+}
@@ -0,0 +1,59 @@
+use crate::{app::AppState, error::ApiError};
+use actix_web::{dev::Payload, http::header, web, FromRequest, HttpRequest};
+use std::future::{ready, Ready};
+use subtle::ConstantTimeEq;
+
+/// Header accepted as an alternative to `Authorization: Bearer <key>`.
+pub const X_API_KEY: &str = "x-api-key";
+
+/// Proof that a request carried a valid API key, in either an `Authorization: Bearer` or an
+/// `X-API-Key` header. Extraction fails with `401 Unauthorized` otherwise, and always fails when
+/// no keys are configured.
+#[derive(Debug)]
+pub struct ApiKey;
+
+impl ApiKey {
+    fn from_request(req: &HttpRequest) -> Result<Self, ApiError> {
+        let keys = req
+            .app_data::<web::Data<AppState>>()
+            .map(|state| state.config.admin_keys.as_slice())
+            .unwrap_or_default();
+        if keys.is_empty() {
+            tracing::warn!("Rejected authenticated request: PHOSPHOR_ADMIN_KEY is not set");
+            return Err(ApiError::Unauthorized(
+                "Admin endpoints are disabled".to_string(),
+            ));
+        }
+
+        let headers = req.headers();
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .or_else(|| headers.get(X_API_KEY).and_then(|h| h.to_str().ok()))
+            .map(str::trim);
+
+        // Every configured key is compared so that the time taken doesn't reveal which matched.
+        let valid = token.is_some_and(|token| {
+            keys.iter().fold(0u8, |matched, key| {
+                matched | token.as_bytes().ct_eq(key.expose().as_bytes()).unwrap_u8()
+            }) == 1
+        });
+        if valid {
+            Ok(ApiKey)
+        } else {
+            Err(ApiError::Unauthorized(
+                "Missing or invalid API key".to_string(),
+            ))
+        }
+    }
+}
+
+impl FromRequest for ApiKey {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(ApiKey::from_request(req))
+    }
+}
@@ -0,0 +1,375 @@
+use std::{env, fmt, net::Ipv4Addr, str::FromStr};
+use thiserror::Error;
+
+const DEFAULT_PORT: u16 = 8080;
+/// The app requires a minimum of 3 workers to run the docs server, dispatch, and at least one
+/// request handler.
+const MIN_WORKERS: usize = 3;
+const DEFAULT_WORKERS: usize = 8;
+/// The page size of icon listings and searches that don't specify a `limit`.
+pub const DEFAULT_PAGE_SIZE: u64 = 100;
+/// The most icons returned by one page of a listing or search, or by a bulk export.
+pub const MAX_PAGE_SIZE: u64 = 500;
+const DEFAULT_SEARCH_THRESHOLD: f64 = 0.2;
+const DEFAULT_SEARCH_MIN_THRESHOLD: f64 = 0.0;
+const DEFAULT_SEARCH_MAX_DISTANCE: u32 = 2;
+const DEFAULT_SYNONYMS_PATH: &str = "./synonyms.json";
+const DEFAULT_APPSHEET_REGION: &str = "www.appsheet.com";
+const DEFAULT_APPSHEET_APP_ID: &str = "14ed274a-6160-4aae-8ee2-9f746dc77f64";
+const DEFAULT_APPSHEET_TABLE_NAME: &str = "Icon Inventory";
+/// One day, in seconds.
+pub const DEFAULT_ICON_MAX_AGE: u32 = 60 * 60 * 24;
+/// Five minutes, in seconds.
+pub const DEFAULT_QUERY_MAX_AGE: u32 = 60 * 5;
+const DEFAULT_RATE_LIMIT: u32 = 600;
+const DEFAULT_SHUTDOWN_TIMEOUT: u64 = 30;
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("{0} must be set")]
+    Missing(&'static str),
+    #[error("Invalid {name}={value:?}: expected {expected}")]
+    Invalid {
+        name: &'static str,
+        value: String,
+        expected: &'static str,
+    },
+}
+
+/// A configuration value that is never printed, such as a key or a URL with credentials.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Secret(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+/// Server configuration, read once from the environment at startup.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Interface to listen on. Set with `HOST`; defaults to all interfaces.
+    pub host: String,
+    /// Set with `PORT`; defaults to 8080.
+    pub port: u16,
+    /// Set with `PHOSPHOR_WORKERS`; defaults to 8, and must be at least 3.
+    pub workers: usize,
+    /// Serve metrics on this address rather than alongside the API. Set with
+    /// `PHOSPHOR_METRICS_ADDR`.
+    pub metrics_addr: Option<String>,
+    /// Postgres connection string. Set with `DATABASE_URL`, which is required.
+    pub database_url: Secret,
+    /// Overrides the current library version, used during staged rollouts when the database
+    /// already contains icons from an upcoming release. Icons released after it are left out of
+    /// the library info, latest icons, feed, and version changes. Set with
+    /// `CURRENT_LIBRARY_VERSION`.
+    pub current_version: Option<f64>,
+    /// The minimum trigram similarity for a search result to be returned, from 0 to 1. Set with
+    /// `PHOSPHOR_SEARCH_THRESHOLD`; defaults to 0.2.
+    pub search_threshold: f64,
+    /// The lowest similarity threshold a search may ask for with `threshold`, from 0 to 1, so that
+    /// clients can't flood results with noise. Also applies to the default threshold. Set with
+    /// `PHOSPHOR_SEARCH_MIN_THRESHOLD`; defaults to 0.
+    pub search_min_threshold: f64,
+    /// The most typos, as a Levenshtein distance, tolerated between a search term and an icon name
+    /// or alias. Shorter terms tolerate fewer. Set with `PHOSPHOR_SEARCH_MAX_DISTANCE`; defaults
+    /// to 2, and 0 disables typo tolerance.
+    pub search_max_distance: u32,
+    /// A JSON file of search synonyms, described in [`crate::synonyms`]. Set with
+    /// `PHOSPHOR_SYNONYMS_PATH`; defaults to `./synonyms.json`.
+    pub synonyms_path: String,
+    /// Origins allowed to make cross-origin requests, or `*` for any. Set with
+    /// `CORS_ALLOWED_ORIGINS` as a comma-separated list; defaults to `*`.
+    pub cors_allowed_origins: Vec<String>,
+    /// `Cache-Control` max-age for responses describing a single icon, in seconds. Set with
+    /// `PHOSPHOR_ICON_MAX_AGE`; defaults to one day.
+    pub icon_max_age: u32,
+    /// `Cache-Control` max-age for listings, searches, and metadata, which change whenever the
+    /// catalog is synced, in seconds. Set with `PHOSPHOR_QUERY_MAX_AGE`; defaults to five minutes.
+    pub query_max_age: u32,
+    /// Requests per minute allowed from a single client IP, which may also burst up to this many
+    /// requests at once. Set with `PHOSPHOR_RATE_LIMIT`; defaults to 600, and 0 disables rate
+    /// limiting.
+    pub rate_limit: u32,
+    /// Identify clients by the `Forwarded`/`X-Forwarded-For` headers rather than the peer address.
+    /// Only enable this behind a proxy that sets them, as clients can forge them. Set with
+    /// `PHOSPHOR_TRUST_PROXY`.
+    pub trust_proxy: bool,
+    /// How long to wait for in-flight requests to finish after a shutdown signal before they are
+    /// dropped, in seconds. Set with `PHOSPHOR_SHUTDOWN_TIMEOUT`; defaults to 30.
+    pub shutdown_timeout: u64,
+    /// Keys accepted by the admin endpoints, which are disabled when there are none. Set with
+    /// `PHOSPHOR_ADMIN_KEY` as a comma-separated list, so that keys can be rotated without
+    /// downtime.
+    pub admin_keys: Vec<Secret>,
+    pub page_size: PageSize,
+    pub sync: SyncConfig,
+    pub appsheet: AppSheetConfig,
+}
+
+/// Page sizes shared by every paginated endpoint, so that limits are governed in one place.
+#[derive(Clone, Copy, Debug)]
+pub struct PageSize {
+    /// Set with `PHOSPHOR_DEFAULT_PAGE_SIZE`; defaults to 100.
+    pub default: u64,
+    /// Set with `PHOSPHOR_MAX_PAGE_SIZE`; defaults to 500, and must be at least the default.
+    pub max: u64,
+}
+
+impl Default for PageSize {
+    fn default() -> Self {
+        PageSize {
+            default: DEFAULT_PAGE_SIZE,
+            max: MAX_PAGE_SIZE,
+        }
+    }
+}
+
+impl PageSize {
+    /// The effective page size for a request that asked for `requested` items: the default when
+    /// unspecified, and never less than 1 or more than the max.
+    pub fn clamp_limit(&self, requested: Option<u64>) -> u64 {
+        requested.unwrap_or(self.default).clamp(1, self.max.max(1))
+    }
+
+    /// An endpoint's own page size, with its default and max lowered to the configured max where
+    /// they exceed it.
+    pub fn capped_by(self, configured: &PageSize) -> PageSize {
+        PageSize {
+            default: self.default.min(configured.max),
+            max: self.max.min(configured.max),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SyncConfig {
+    /// Sync the AppSheet table on startup. Set with `PHOSPHOR_TABLE_SYNC`.
+    pub table: bool,
+    /// Only upsert table rows last updated in or after the newest version already in the
+    /// database. Set with `PHOSPHOR_INCREMENTAL_SYNC`.
+    pub incremental: bool,
+    /// Fail a table sync, rather than skipping the affected rows, when several rows share an icon
+    /// name. Set with `PHOSPHOR_STRICT_SYNC`.
+    pub strict: bool,
+    /// Sync SVGs from `./core/assets` on startup. Set with `PHOSPHOR_ASSETS_SYNC`.
+    pub assets: bool,
+    /// Rewrite hardcoded colors in synced SVGs to `currentColor`. Set with
+    /// `PHOSPHOR_NORMALIZE_COLOR`; enabled by default.
+    pub normalize_color: bool,
+    /// Minify synced SVGs. Set with `PHOSPHOR_OPTIMIZE_SVG`; enabled by default.
+    pub optimize_svg: bool,
+    /// Receives a POST describing each completed sync. Set with `PHOSPHOR_SYNC_WEBHOOK_URL`.
+    pub webhook_url: Option<Secret>,
+}
+
+/// Identifies the AppSheet table that icons are synced from. The region, app, and table default
+/// to the Phosphor inventory, and can be overridden to sync from a different app.
+#[derive(Clone, Debug)]
+pub struct AppSheetConfig {
+    /// Set with `GOOGLE_APPSHEET_REGION`.
+    pub region: String,
+    /// Set with `GOOGLE_APPSHEET_APP_ID`.
+    pub app_id: String,
+    /// Set with `GOOGLE_APPSHEET_TABLE_NAME`.
+    pub table_name: String,
+    /// Set with `GOOGLE_APPSHEET_APPLICATION_KEY`, which is only required to sync the table.
+    pub application_key: Option<Secret>,
+}
+
+fn var(name: &'static str) -> Result<Option<String>, ConfigError> {
+    match env::var(name) {
+        Ok(val) if val.trim().is_empty() => Ok(None),
+        Ok(val) => Ok(Some(val.trim().to_string())),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(val)) => Err(ConfigError::Invalid {
+            name,
+            value: val.to_string_lossy().into_owned(),
+            expected: "a UTF-8 string",
+        }),
+    }
+}
+
+fn parse<T: FromStr>(
+    name: &'static str,
+    expected: &'static str,
+    valid: impl Fn(&T) -> bool,
+) -> Result<Option<T>, ConfigError> {
+    var(name)?
+        .map(|value| match value.parse::<T>() {
+            Ok(v) if valid(&v) => Ok(v),
+            _ => Err(ConfigError::Invalid {
+                name,
+                value,
+                expected,
+            }),
+        })
+        .transpose()
+}
+
+fn flag(name: &'static str, default: bool) -> Result<bool, ConfigError> {
+    match var(name)? {
+        None => Ok(default),
+        Some(val) => match val.to_ascii_lowercase().as_str() {
+            "true" | "1" => Ok(true),
+            "false" | "0" => Ok(false),
+            _ => Err(ConfigError::Invalid {
+                name,
+                value: val,
+                expected: "true or false",
+            }),
+        },
+    }
+}
+
+/// A comma-separated list of origins such as `https://phosphoricons.com`, or `*`.
+fn origins(name: &'static str) -> Result<Vec<String>, ConfigError> {
+    let Some(value) = var(name)? else {
+        return Ok(vec!["*".to_string()]);
+    };
+    let origins = value
+        .split(',')
+        .map(|origin| origin.trim().trim_end_matches('/').to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect::<Vec<_>>();
+    let valid = |origin: &String| {
+        origin == "*" || origin.starts_with("https://") || origin.starts_with("http://")
+    };
+    if origins.is_empty() || !origins.iter().all(valid) {
+        return Err(ConfigError::Invalid {
+            name,
+            value,
+            expected: "* or a comma-separated list of http(s) origins",
+        });
+    }
+    Ok(origins)
+}
+
+/// A comma-separated list of secrets, ignoring empty entries.
+fn secrets(name: &'static str) -> Result<Vec<Secret>, ConfigError> {
+    Ok(var(name)?
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|secret| !secret.is_empty())
+        .map(|secret| Secret(secret.to_string()))
+        .collect())
+}
+
+fn page_size() -> Result<PageSize, ConfigError> {
+    let max =
+        parse("PHOSPHOR_MAX_PAGE_SIZE", "a positive integer", |&n| n > 0)?.unwrap_or(MAX_PAGE_SIZE);
+    let default = parse(
+        "PHOSPHOR_DEFAULT_PAGE_SIZE",
+        "a positive integer no greater than PHOSPHOR_MAX_PAGE_SIZE",
+        |&n| n > 0 && n <= max,
+    )?
+    .unwrap_or(DEFAULT_PAGE_SIZE.min(max));
+    Ok(PageSize { default, max })
+}
+
+/// An AppSheet URL segment, which must not contain any of the `forbidden` characters.
+fn url_segment(
+    name: &'static str,
+    default: &str,
+    forbidden: &[char],
+) -> Result<String, ConfigError> {
+    match var(name)? {
+        None => Ok(default.to_string()),
+        Some(value) if value.contains(forbidden) => Err(ConfigError::Invalid {
+            name,
+            value,
+            expected: "a single URL path segment",
+        }),
+        Some(value) => Ok(value),
+    }
+}
+
+impl Config {
+    /// Read and validate the configuration, failing on the first missing or malformed setting.
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(Config {
+            host: var("HOST")?.unwrap_or_else(|| Ipv4Addr::UNSPECIFIED.to_string()),
+            port: parse("PORT", "a port number", |_| true)?.unwrap_or(DEFAULT_PORT),
+            workers: parse("PHOSPHOR_WORKERS", "an integer of at least 3", |&w| {
+                w >= MIN_WORKERS
+            })?
+            .unwrap_or(DEFAULT_WORKERS),
+            metrics_addr: var("PHOSPHOR_METRICS_ADDR")?,
+            database_url: var("DATABASE_URL")?
+                .map(Secret)
+                .ok_or(ConfigError::Missing("DATABASE_URL"))?,
+            current_version: parse("CURRENT_LIBRARY_VERSION", "a version such as 2.1", |v| {
+                f64::is_finite(*v)
+            })?,
+            search_threshold: parse("PHOSPHOR_SEARCH_THRESHOLD", "a number from 0 to 1", |v| {
+                (0.0..=1.0).contains(v)
+            })?
+            .unwrap_or(DEFAULT_SEARCH_THRESHOLD),
+            search_min_threshold: parse(
+                "PHOSPHOR_SEARCH_MIN_THRESHOLD",
+                "a number from 0 to 1",
+                |v| (0.0..=1.0).contains(v),
+            )?
+            .unwrap_or(DEFAULT_SEARCH_MIN_THRESHOLD),
+            search_max_distance: parse(
+                "PHOSPHOR_SEARCH_MAX_DISTANCE",
+                "an integer from 0 to 5",
+                |&d| d <= 5,
+            )?
+            .unwrap_or(DEFAULT_SEARCH_MAX_DISTANCE),
+            synonyms_path: var("PHOSPHOR_SYNONYMS_PATH")?
+                .unwrap_or_else(|| DEFAULT_SYNONYMS_PATH.to_string()),
+            cors_allowed_origins: origins("CORS_ALLOWED_ORIGINS")?,
+            icon_max_age: parse("PHOSPHOR_ICON_MAX_AGE", "a number of seconds", |_| true)?
+                .unwrap_or(DEFAULT_ICON_MAX_AGE),
+            query_max_age: parse("PHOSPHOR_QUERY_MAX_AGE", "a number of seconds", |_| true)?
+                .unwrap_or(DEFAULT_QUERY_MAX_AGE),
+            rate_limit: parse(
+                "PHOSPHOR_RATE_LIMIT",
+                "a number of requests per minute",
+                |_| true,
+            )?
+            .unwrap_or(DEFAULT_RATE_LIMIT),
+            trust_proxy: flag("PHOSPHOR_TRUST_PROXY", false)?,
+            shutdown_timeout: parse("PHOSPHOR_SHUTDOWN_TIMEOUT", "a number of seconds", |_| true)?
+                .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT),
+            admin_keys: secrets("PHOSPHOR_ADMIN_KEY")?,
+            page_size: page_size()?,
+            sync: SyncConfig {
+                table: flag("PHOSPHOR_TABLE_SYNC", false)?,
+                incremental: flag("PHOSPHOR_INCREMENTAL_SYNC", false)?,
+                strict: flag("PHOSPHOR_STRICT_SYNC", false)?,
+                assets: flag("PHOSPHOR_ASSETS_SYNC", false)?,
+                normalize_color: flag("PHOSPHOR_NORMALIZE_COLOR", true)?,
+                optimize_svg: flag("PHOSPHOR_OPTIMIZE_SVG", true)?,
+                webhook_url: var("PHOSPHOR_SYNC_WEBHOOK_URL")?.map(Secret),
+            },
+            appsheet: AppSheetConfig {
+                region: url_segment(
+                    "GOOGLE_APPSHEET_REGION",
+                    DEFAULT_APPSHEET_REGION,
+                    &['/', ':'],
+                )?,
+                app_id: url_segment("GOOGLE_APPSHEET_APP_ID", DEFAULT_APPSHEET_APP_ID, &['/'])?,
+                table_name: url_segment(
+                    "GOOGLE_APPSHEET_TABLE_NAME",
+                    DEFAULT_APPSHEET_TABLE_NAME,
+                    &['/'],
+                )?,
+                application_key: var("GOOGLE_APPSHEET_APPLICATION_KEY")?.map(Secret),
+            },
+        })
+    }
+}
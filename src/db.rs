@@ -1,26 +1,100 @@
+use crate::config::{Config, PageSize};
 use crate::entities::{icons, svgs};
-use crate::icons::{Category, IconStatus, LibraryInfo};
-use sea_orm::sea_query::OnConflict;
+use crate::icons::{
+    Category, CategoryCount, Deprecation, FigmaCategory, IconName, IconStatus, IconWeight,
+    LibraryInfo, TagCount,
+};
+use crate::metrics;
+use crate::svgs::Svg;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sea_orm::sea_query::{NullOrdering, OnConflict};
 use sea_orm::{
-    prelude::*, Condition, Database, DatabaseConnection, Order, QueryOrder, QuerySelect,
+    prelude::*, ActiveValue, Condition, ConnectionTrait, Database, DatabaseConnection,
+    FromQueryResult, Order, QueryOrder, QueryResult, QuerySelect, QueryTrait, Statement,
+    TransactionTrait,
 };
-use serde::{Deserialize, Deserializer};
-use std::collections::HashMap;
-use std::env;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::str::FromStr;
+use std::sync::LazyLock;
 use utoipa::{IntoParams, ToSchema};
 
+/// How much a match on each field counts towards relevance, so that an icon named after the
+/// search term outranks one that merely carries it as a tag.
+pub const NAME_WEIGHT: f64 = 1.0;
+pub const ALIAS_WEIGHT: f64 = 0.8;
+pub const TAG_WEIGHT: f64 = 0.6;
+
+/// The relevance of an icon to a search, as the best weighted trigram similarity between any of
+/// the terms in `$1` and the icon's name, alias, or any of its tags. A name or alias within `$3`
+/// edits of the original term `$2` also scores the share of the term's characters left intact, so
+/// that misspellings like `calender` still rank `calendar` highly.
+static SEARCH_SCORE: LazyLock<String> = LazyLock::new(|| {
+    format!(
+        "GREATEST(\
+            (SELECT COALESCE(MAX(GREATEST(\
+               {NAME_WEIGHT}::float8 * similarity(name, term), \
+               {ALIAS_WEIGHT}::float8 * similarity(COALESCE(alias, ''), term), \
+               {TAG_WEIGHT}::float8 * (SELECT COALESCE(MAX(similarity(tag, term)), 0) \
+                 FROM unnest(tags) AS tag)\
+             )), 0) \
+             FROM unnest($1::text[]) AS term), \
+            (SELECT COALESCE(MAX(w * (1 - d::float8 / length($2))), 0) \
+             FROM (VALUES \
+               (levenshtein_less_equal(name, $2, $3), {NAME_WEIGHT}::float8), \
+               (levenshtein_less_equal(COALESCE(alias, ''), $2, $3), {ALIAS_WEIGHT}::float8)\
+             ) AS typo(d, w) \
+             WHERE d <= $3)\
+        )::float8"
+    )
+});
+
+/// Search terms shorter than this are matched without typo tolerance, since nearly every short
+/// name is within an edit or two of them.
+const MIN_TYPO_TERM_LENGTH: usize = 4;
+
+/// Names longer than this get no "did you mean" suggestions when not found.
+const MAX_SUGGESTION_TERM_LENGTH: usize = 64;
+
+/// The longest search term accepted, in characters. Postgres' `levenshtein_less_equal` rejects
+/// arguments over 255 characters, and no icon name comes close to this.
+pub const MAX_SEARCH_TERM_LENGTH: usize = 128;
+
 #[derive(Debug)]
 pub struct Db {
     pub conn: DatabaseConnection,
+    /// An optional override for the current library version, used during staged rollouts when
+    /// the database already contains icons from an upcoming release.
+    pub current_version: Option<f64>,
+    /// The minimum trigram similarity for a search result to be returned, unless the search asks
+    /// for another.
+    pub search_threshold: f64,
+    /// The lowest similarity threshold a search may use.
+    pub search_min_threshold: f64,
+    /// The most edits a search term may be from an icon name or alias to match it.
+    pub search_max_distance: u32,
+    /// The default and maximum number of results per page.
+    pub page_size: PageSize,
 }
 
 impl Db {
-    #[tracing::instrument(level = "info")]
-    pub async fn init() -> Result<Self, sea_orm::DbErr> {
-        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
-        let conn = Database::connect(database_url).await?;
-        Ok(Self { conn })
+    #[tracing::instrument(level = "info", skip_all)]
+    pub async fn init(config: &Config) -> Result<Self, sea_orm::DbErr> {
+        let mut conn = Database::connect(config.database_url.expose()).await?;
+        conn.set_metric_callback(|info| {
+            metrics::observe_query(&info.statement.sql, info.elapsed, info.failed)
+        });
+        if let Some(version) = config.current_version {
+            tracing::info!("CURRENT_LIBRARY_VERSION={}", version);
+        }
+        Ok(Self {
+            conn,
+            current_version: config.current_version,
+            search_threshold: config.search_threshold,
+            search_min_threshold: config.search_min_threshold,
+            search_max_distance: config.search_max_distance,
+            page_size: config.page_size,
+        })
     }
 
     #[tracing::instrument(level = "info", skip(self))]
@@ -28,10 +102,156 @@ impl Db {
         self.conn.ping().await
     }
 
+    /// Publish the connection pool's current size and idle connections as metrics.
+    pub fn record_pool_usage(&self) {
+        if let DatabaseConnection::SqlxPostgresPoolConnection(_) = self.conn {
+            let pool = self.conn.get_postgres_connection_pool();
+            metrics::set_db_pool(pool.size(), pool.num_idle());
+        }
+    }
+
+    /// The tables the server queries that don't exist in the database, which is the case until
+    /// migrations have been run against it.
     #[tracing::instrument(level = "info", skip(self))]
-    pub async fn dump_stats(&self) -> Result<(), ()> {
-        tracing::info!("Conn: {:?}", self.conn);
-        Ok(())
+    pub async fn missing_tables(&self) -> Result<Vec<String>, DbErr> {
+        use sea_orm::EntityName;
+
+        let stmt = Statement::from_sql_and_values(
+            self.conn.get_database_backend(),
+            "SELECT name FROM unnest($1::text[]) AS name WHERE to_regclass(name) IS NULL",
+            [vec![
+                icons::Entity.table_name().to_string(),
+                svgs::Entity.table_name().to_string(),
+            ]
+            .into()],
+        );
+        self.conn
+            .query_all(stmt)
+            .await?
+            .into_iter()
+            .map(|row| row.try_get::<String>("", "name"))
+            .collect()
+    }
+
+    /// Whether the database is ready to serve requests: migrated, and with icons loaded.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn readiness(&self) -> Result<Readiness, DbErr> {
+        if !self.missing_tables().await?.is_empty() {
+            return Ok(Readiness {
+                tables_present: false,
+                icon_count: None,
+            });
+        }
+        let icon_count = icons::Entity::find().count(&self.conn).await?;
+        Ok(Readiness {
+            tables_present: true,
+            icon_count: Some(icon_count),
+        })
+    }
+
+    /// Close every pooled connection, waiting for checked-out connections to be returned. Clones
+    /// of the connection share its pool, so this closes it for all of them.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn close(&self) -> Result<(), DbErr> {
+        self.conn.clone().close().await
+    }
+
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn dump_stats(&self) -> Result<DbStats, DbErr> {
+        let icons = icons::Entity::find().count(&self.conn).await?;
+        let published = icons::Entity::find()
+            .filter(icons::Column::Published.eq(true))
+            .count(&self.conn)
+            .await?;
+        let svgs = svgs::Entity::find().count(&self.conn).await?;
+
+        let by_status = icons::Entity::find()
+            .select_only()
+            .column(icons::Column::Status)
+            .column_as(icons::Column::Id.count(), "count")
+            .group_by(icons::Column::Status)
+            .into_tuple::<(String, i64)>()
+            .all(&self.conn)
+            .await?
+            .into_iter()
+            .map(|(status, count)| (status, count as u64))
+            .collect();
+
+        let stmt = Statement::from_string(
+            self.conn.get_database_backend(),
+            "SELECT search_category, COUNT(*) AS count \
+             FROM icons, unnest(search_categories) AS search_category \
+             GROUP BY search_category",
+        );
+        let by_category = self
+            .conn
+            .query_all(stmt)
+            .await?
+            .into_iter()
+            .map(|row| {
+                Ok((
+                    row.try_get::<String>("", "search_category")?,
+                    row.try_get::<i64>("", "count")? as u64,
+                ))
+            })
+            .collect::<Result<_, DbErr>>()?;
+
+        let weights = self.weight_summary().await?;
+
+        Ok(DbStats {
+            icons,
+            published,
+            svgs,
+            by_status,
+            by_category,
+            weights,
+            search_threshold: self.effective_threshold(None),
+            search_min_threshold: self.search_min_threshold,
+        })
+    }
+
+    /// How completely published icons are covered by SVGs, counting the weights stored for each.
+    async fn weight_summary(&self) -> Result<WeightSummary, DbErr> {
+        let backend = self.conn.get_database_backend();
+        let stmt = Statement::from_sql_and_values(
+            backend,
+            "SELECT COUNT(*) AS count FROM icons \
+             WHERE published AND id IN (\
+               SELECT icon_id FROM svgs GROUP BY icon_id HAVING COUNT(DISTINCT weight) = $1\
+             )",
+            [Value::from(IconWeight::COUNT as i64)],
+        );
+        let complete = match self.conn.query_one(stmt).await? {
+            Some(row) => row.try_get::<i64>("", "count")? as u64,
+            None => 0,
+        };
+
+        let weights = IconWeight::ALL
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let stmt = Statement::from_sql_and_values(
+            backend,
+            "SELECT w.weight, COUNT(*) AS count \
+             FROM icons, unnest($1::text[]) AS w(weight) \
+             WHERE published AND NOT EXISTS (\
+               SELECT 1 FROM svgs WHERE svgs.icon_id = icons.id AND svgs.weight = w.weight\
+             ) \
+             GROUP BY w.weight",
+            [Value::from(weights.clone())],
+        );
+        let mut missing = weights
+            .into_iter()
+            .map(|weight| (weight, 0))
+            .collect::<BTreeMap<_, _>>();
+        for row in self.conn.query_all(stmt).await? {
+            missing.insert(
+                row.try_get::<String>("", "weight")?,
+                row.try_get::<i64>("", "count")? as u64,
+            );
+        }
+
+        Ok(WeightSummary { complete, missing })
     }
 
     #[tracing::instrument(level = "info")]
@@ -39,16 +259,15 @@ impl Db {
         let mut cond = Condition::all();
 
         if let Some(name) = &query.name {
-            let comp = if name.starts_with('*') || name.ends_with('*') {
+            if name.starts_with('*') || name.ends_with('*') {
                 let trimmed = name.trim_matches('*');
-                if trimmed.is_empty() {
-                    return cond; // If the name is just '*', return empty condition
+                // A name of just `*` matches every icon, so it adds no condition.
+                if !trimmed.is_empty() {
+                    cond = cond.add(icons::Column::Name.like(format!("%{}%", trimmed)));
                 }
-                icons::Column::Name.like(format!("%{}%", trimmed))
             } else {
-                icons::Column::Name.eq(name)
-            };
-            cond = cond.add(comp);
+                cond = cond.add(icons::Column::Name.eq(name));
+            }
         }
 
         match &query.published {
@@ -108,6 +327,23 @@ impl Db {
             }
         }
 
+        if let Some(code) = &query.code {
+            match code {
+                CodeQuery::Exact(v) => {
+                    cond = cond.add(icons::Column::Code.eq(*v));
+                }
+                CodeQuery::Range(a, b) => {
+                    cond = cond.add(icons::Column::Code.between(*a, *b));
+                }
+                CodeQuery::LessThanOrEqual(v) => {
+                    cond = cond.add(icons::Column::Code.lte(*v));
+                }
+                CodeQuery::GreaterThanOrEqual(v) => {
+                    cond = cond.add(icons::Column::Code.gte(*v));
+                }
+            }
+        }
+
         if let Some(status) = &query.status {
             cond = cond.add(icons::Column::Status.is_in(status.iter().map(|s| s.to_string())));
         }
@@ -123,33 +359,178 @@ impl Db {
             cond = cond.add(Expr::cust_with_values("tags && $1", [tags.clone()]));
         }
 
+        if let Some(category) = &query.exclude_category {
+            cond = cond.add(Expr::cust_with_values(
+                "NOT (search_categories && $1)",
+                [category.iter().map(|c| c.to_string()).collect::<Vec<_>>()],
+            ));
+        }
+
+        if let Some(tags) = &query.exclude_tags {
+            cond = cond.add(Expr::cust_with_values("NOT (tags && $1)", [tags.clone()]));
+        }
+
+        if let Some(weight) = &query.has_weight {
+            cond = cond.add(Expr::cust_with_values(
+                "EXISTS (SELECT 1 FROM svgs WHERE svgs.icon_id = icons.id AND svgs.weight = $1)",
+                [weight.to_string()],
+            ));
+        }
+
+        match query.has_notes {
+            Some(true) => cond = cond.add(icons::Column::Notes.is_not_null()),
+            Some(false) => cond = cond.add(icons::Column::Notes.is_null()),
+            None => {}
+        }
+
         cond
     }
 
+    /// Resolve the requested sort into columns to order by, in sequence. Fields without an
+    /// explicit direction use `dir`. The icon ID is always appended as a final tiebreaker so that
+    /// icons with equal sort keys come back in a stable order across pages.
     #[tracing::instrument(level = "info")]
-    fn build_order_from_params(query: &IconQuery) -> (icons::Column, Order) {
-        let order_column = match query.order {
-            None | Some(OrderColumn::Name) => icons::Column::Name,
-            Some(OrderColumn::Status) => icons::Column::Status,
-            Some(OrderColumn::Release) => icons::Column::ReleasedAt,
-            Some(OrderColumn::Code) => icons::Column::Code,
+    fn build_order_from_params(query: &IconQuery) -> Vec<(icons::Column, Order)> {
+        let default_direction = query.dir.unwrap_or_default();
+        let fields = match &query.order {
+            Some(fields) if !fields.is_empty() => fields.clone(),
+            _ => vec![OrderField::default()],
         };
 
-        let order_direction = match query.dir {
-            Some(OrderDirection::Asc) | None => Order::Asc,
-            Some(OrderDirection::Desc) => Order::Desc,
-        };
+        let mut order = fields
+            .into_iter()
+            .map(|field| {
+                let column = match field.column {
+                    OrderColumn::Name => icons::Column::Name,
+                    OrderColumn::Status => icons::Column::Status,
+                    OrderColumn::Release => icons::Column::ReleasedAt,
+                    OrderColumn::Code => icons::Column::Code,
+                    OrderColumn::Updated => icons::Column::LastUpdatedAt,
+                };
+                let direction = match field.dir.unwrap_or(default_direction) {
+                    OrderDirection::Asc => Order::Asc,
+                    OrderDirection::Desc => Order::Desc,
+                };
+                (column, direction)
+            })
+            .collect::<Vec<_>>();
+        order.push((icons::Column::Id, Order::Asc));
+        order
+    }
+
+    /// Build a keyset condition selecting the rows that sort strictly after `cursor` under
+    /// `order`, mirroring the `NULLS LAST` ordering applied in [`Db::select_icons`].
+    fn build_cursor_condition(order: &[(icons::Column, Order)], cursor: &Cursor) -> Condition {
+        let is_at =
+            |column: icons::Column, value: &serde_json::Value| match cursor_value(column, value) {
+                Some(value) => column.eq(value),
+                None => column.is_null(),
+            };
 
-        (order_column, order_direction)
+        let mut cond = Condition::any();
+        for (k, ((column, dir), value)) in order.iter().zip(&cursor.key).enumerate() {
+            // Nothing sorts after a NULL except other NULLs, which the next column decides.
+            let Some(value) = cursor_value(*column, value) else {
+                continue;
+            };
+            let after = match dir {
+                Order::Desc => column.lt(value),
+                _ => column.gt(value),
+            };
+            let mut branch = Condition::all();
+            for ((prev, _), prev_value) in order.iter().zip(&cursor.key).take(k) {
+                branch = branch.add(is_at(*prev, prev_value));
+            }
+            cond = cond.add(branch.add(Condition::any().add(after).add(column.is_null())));
+        }
+        cond
+    }
+
+    fn select_icons(query: &IconQuery) -> Select<icons::Entity> {
+        let order = Self::build_order_from_params(query);
+        let mut cond = Self::build_condition_from_params(query);
+        if let Some(cursor) = &query.cursor {
+            cond = cond.add(Self::build_cursor_condition(&order, cursor));
+        }
+
+        let mut select = icons::Entity::find().filter(cond);
+        for (ord, dir) in order {
+            // Postgres sorts NULLs first when descending, which would put unreleased icons ahead
+            // of the newest ones in a `dir=desc` release sort.
+            QueryTrait::query(&mut select).order_by_with_nulls(ord, dir, NullOrdering::Last);
+        }
+        if query.cursor.is_none() {
+            select = select.offset(query.offset);
+        }
+        select
     }
 
     #[tracing::instrument(level = "info", skip(self))]
     pub async fn get_icons(&self, query: &IconQuery) -> Result<Vec<icons::Model>, DbErr> {
-        let cond = Self::build_condition_from_params(query);
-        let (ord, dir) = Self::build_order_from_params(query);
+        Self::select_icons(query)
+            .limit(query.limit)
+            .all(&self.conn)
+            .await
+    }
+
+    /// Fetch up to `count` icons matching the query's filters, in random order. Ordering and
+    /// paging parameters are ignored.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_random_icons(
+        &self,
+        query: &IconQuery,
+        count: u64,
+    ) -> Result<Vec<icons::Model>, DbErr> {
         icons::Entity::find()
-            .filter(cond)
-            .order_by(ord, dir)
+            .filter(Self::build_condition_from_params(query))
+            .order_by(Expr::cust("random()"), Order::Asc)
+            .limit(count)
+            .all(&self.conn)
+            .await
+    }
+
+    /// Fetch a page of icons along with a cursor for the next page, or `None` if this is the last
+    /// page. The query should have a `limit`.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_icon_page(
+        &self,
+        query: &IconQuery,
+    ) -> Result<(Vec<icons::Model>, Option<Cursor>), DbErr> {
+        let limit = self.page_size.clamp_limit(query.limit);
+        let mut models = Self::select_icons(query)
+            .limit(limit + 1)
+            .all(&self.conn)
+            .await?;
+        if models.len() as u64 <= limit {
+            return Ok((models, None));
+        }
+
+        models.truncate(limit as usize);
+        let cursor = models.last().map(|last| Self::cursor_after(query, last));
+        Ok((models, cursor))
+    }
+
+    /// The cursor resuming `query` after `last`.
+    pub fn cursor_after(query: &IconQuery, last: &icons::Model) -> Cursor {
+        let order = Self::build_order_from_params(query);
+        Cursor {
+            order: order_signature(&order),
+            key: order
+                .iter()
+                .map(|(column, _)| sort_key_value(last, *column))
+                .collect(),
+        }
+    }
+
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_latest_icons(&self, limit: u64) -> Result<Vec<icons::Model>, DbErr> {
+        icons::Entity::find()
+            .filter(icons::Column::Published.eq(true))
+            .filter(icons::Column::ReleasedAt.is_not_null())
+            .filter(self.released_by_current_version())
+            .order_by(icons::Column::ReleasedAt, Order::Desc)
+            .order_by(icons::Column::Id, Order::Desc)
+            .limit(limit)
             .all(&self.conn)
             .await
     }
@@ -168,6 +549,43 @@ impl Db {
             .await
     }
 
+    /// Find the icon that replaced the one previously named `alias`.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_icon_by_alias(&self, alias: &str) -> Result<Option<icons::Model>, DbErr> {
+        icons::Entity::find()
+            .filter(icons::Column::Alias.eq(alias))
+            .one(&self.conn)
+            .await
+    }
+
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_icon_ids_by_name(&self) -> Result<HashMap<String, i32>, DbErr> {
+        icons::Entity::find()
+            .select_only()
+            .column(icons::Column::Name)
+            .column(icons::Column::Id)
+            .into_tuple::<(String, i32)>()
+            .all(&self.conn)
+            .await
+            .map(|rows| rows.into_iter().collect())
+    }
+
+    /// Map the name of each published icon with a font codepoint to that codepoint, loading only
+    /// the two columns.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_codepoints(&self) -> Result<BTreeMap<String, i32>, DbErr> {
+        icons::Entity::find()
+            .select_only()
+            .column(icons::Column::Name)
+            .column(icons::Column::Code)
+            .filter(icons::Column::Published.eq(true))
+            .filter(icons::Column::Code.is_not_null())
+            .into_tuple::<(String, i32)>()
+            .all(&self.conn)
+            .await
+            .map(|rows| rows.into_iter().collect())
+    }
+
     #[tracing::instrument(level = "info", skip(self))]
     pub async fn get_icon_by_id(&self, id: i32) -> Result<Option<icons::Model>, DbErr> {
         icons::Entity::find()
@@ -176,6 +594,14 @@ impl Db {
             .await
     }
 
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_icons_by_ids(&self, ids: &[i32]) -> Result<Vec<icons::Model>, DbErr> {
+        icons::Entity::find()
+            .filter(icons::Column::Id.is_in(ids.iter().copied()))
+            .all(&self.conn)
+            .await
+    }
+
     #[tracing::instrument(level = "info", skip(self))]
     pub async fn get_icon_by_rid(&self, rid: &str) -> Result<Option<icons::Model>, DbErr> {
         icons::Entity::find()
@@ -184,125 +610,979 @@ impl Db {
             .await
     }
 
-    #[tracing::instrument(level = "info", skip(self))]
-    pub async fn get_icon_by_code(&self, code: i32) -> Result<Option<icons::Model>, DbErr> {
-        icons::Entity::find()
-            .filter(icons::Column::Code.eq(code))
-            .one(&self.conn)
-            .await
-    }
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_icon_by_code(&self, code: i32) -> Result<Option<icons::Model>, DbErr> {
+        icons::Entity::find()
+            .filter(icons::Column::Code.eq(code))
+            .one(&self.conn)
+            .await
+    }
+
+    /// The most recent `Last Updated` version of any icon, published or not.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_last_updated_version(&self) -> Result<Option<f64>, DbErr> {
+        icons::Entity::find()
+            .select_only()
+            .column_as(Expr::col(icons::Column::LastUpdatedAt).max(), "version")
+            .into_tuple::<Option<f64>>()
+            .one(&self.conn)
+            .await
+            .map(Option::flatten)
+    }
+
+    /// Insert the icon, or update the existing icon with the same `rid`. Icons identical to the
+    /// stored row are left untouched.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn upsert_icon(&self, icon: icons::Model) -> Result<UpsertOutcome, DbErr> {
+        let existing = self.get_icon_by_rid(&icon.rid).await?;
+        if let Some(existing) = &existing {
+            let incoming = icons::Model {
+                id: existing.id,
+                ..icon.clone()
+            };
+            if incoming == *existing {
+                return Ok(UpsertOutcome::Unchanged);
+            }
+        }
+
+        let active_model: icons::ActiveModel = icon.into();
+        icons::Entity::insert(active_model)
+            .on_conflict(
+                OnConflict::column(icons::Column::Rid)
+                    .update_column(icons::Column::Name)
+                    .update_column(icons::Column::Status)
+                    .update_column(icons::Column::Category)
+                    .update_column(icons::Column::SearchCategories)
+                    .update_column(icons::Column::Tags)
+                    .update_column(icons::Column::Notes)
+                    .update_column(icons::Column::ReleasedAt)
+                    .update_column(icons::Column::LastUpdatedAt)
+                    .update_column(icons::Column::DeprecatedAt)
+                    .update_column(icons::Column::Published)
+                    .update_column(icons::Column::Alias)
+                    .update_column(icons::Column::Code)
+                    .to_owned(),
+            )
+            .exec(&self.conn)
+            .await?;
+        Ok(match existing {
+            Some(_) => UpsertOutcome::Updated,
+            None => UpsertOutcome::Inserted,
+        })
+    }
+
+    /// Apply a partial update to the icon with the given `rid`, leaving any fields absent from the
+    /// patch untouched. Returns `None` if no such icon exists.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn patch_icon(
+        &self,
+        rid: &str,
+        patch: IconPatch,
+    ) -> Result<Option<icons::Model>, DbErr> {
+        let Some(icon) = self.get_icon_by_rid(rid).await? else {
+            return Ok(None);
+        };
+
+        let mut active_model: icons::ActiveModel = icon.into();
+        if let Some(name) = patch.name {
+            active_model.name = ActiveValue::Set(name);
+        }
+        if let Some(alias) = patch.alias {
+            active_model.alias = ActiveValue::Set(alias);
+        }
+        if let Some(code) = patch.code {
+            active_model.code = ActiveValue::Set(code);
+        }
+        if let Some(status) = patch.status {
+            active_model.status = ActiveValue::Set(status.to_string());
+        }
+        if let Some(search_categories) = patch.search_categories {
+            active_model.search_categories =
+                ActiveValue::Set(search_categories.iter().map(|c| c.to_string()).collect());
+        }
+        if let Some(category) = patch.category {
+            active_model.category = ActiveValue::Set(category.to_string());
+        }
+        if let Some(tags) = patch.tags {
+            active_model.tags = ActiveValue::Set(tags);
+        }
+        if let Some(notes) = patch.notes {
+            active_model.notes = ActiveValue::Set(notes);
+        }
+        if let Some(released_at) = patch.released_at {
+            active_model.released_at = ActiveValue::Set(released_at);
+        }
+        if let Some(last_updated_at) = patch.last_updated_at {
+            active_model.last_updated_at = ActiveValue::Set(last_updated_at);
+        }
+        if let Some(deprecated_at) = patch.deprecated_at {
+            active_model.deprecated_at = ActiveValue::Set(deprecated_at);
+        }
+        if let Some(published) = patch.published {
+            active_model.published = ActiveValue::Set(published);
+        }
+
+        active_model.update(&self.conn).await.map(Some)
+    }
+
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn delete_icon(&self, rid: &str) -> Result<u64, DbErr> {
+        icons::Entity::delete_many()
+            .filter(icons::Column::Rid.eq(rid))
+            .exec(&self.conn)
+            .await
+            .map(|res| res.rows_affected)
+    }
+
+    /// Search published icons for the query's term, or any of the `synonyms` given for it,
+    /// returning a page of the most relevant along with the total number of matches. Only the
+    /// original term is matched with typo tolerance.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn query_icons(
+        &self,
+        query: &IconSearch,
+        synonyms: &[String],
+    ) -> Result<(Vec<ScoredModel>, u64), DbErr> {
+        let q = query.q.trim().to_lowercase();
+        // Overlong terms are rejected by `IconSearch::validate`, but would fail the query outright.
+        if q.is_empty() || q.chars().count() > MAX_SEARCH_TERM_LENGTH {
+            return Ok((vec![], 0));
+        }
+        let terms = std::iter::once(q.clone())
+            .chain(synonyms.iter().cloned())
+            .collect::<Vec<_>>();
+
+        let distance = self.typo_distance(&q);
+        let threshold = self.effective_threshold(query.threshold);
+
+        let matches = icons::Entity::find()
+            .filter(icons::Column::Published.eq(true))
+            .filter(Expr::cust_with_values(
+                format!("{} >= $4", *SEARCH_SCORE),
+                [
+                    Value::from(terms.clone()),
+                    Value::from(q.clone()),
+                    Value::from(distance as i32),
+                    Value::from(threshold),
+                ],
+            ));
+        let total = matches.clone().count(&self.conn).await?;
+
+        // The ID breaks ties between equally relevant icons, so that pages don't overlap.
+        let icons = matches
+            .column_as(
+                Expr::cust_with_values(
+                    SEARCH_SCORE.as_str(),
+                    [
+                        Value::from(terms),
+                        Value::from(q),
+                        Value::from(distance as i32),
+                    ],
+                ),
+                "score",
+            )
+            .order_by_desc(Expr::cust("score"))
+            .order_by_asc(icons::Column::Name)
+            .order_by_asc(icons::Column::Id)
+            .offset(query.offset)
+            .limit(self.page_size.clamp_limit(query.limit))
+            .into_model::<ScoredModel>()
+            .all(&self.conn)
+            .await?;
+        Ok((icons, total))
+    }
+
+    /// The names of up to `limit` published icons most similar to `name`, scored as in
+    /// [`Db::query_icons`] but without synonyms, for suggesting alternatives to a name that
+    /// doesn't exist.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn suggest_names(&self, name: &str, limit: u64) -> Result<Vec<String>, DbErr> {
+        let q = name.trim().to_lowercase();
+        // Long names can't be icon names, and would only make the scoring slower.
+        if q.is_empty() || q.chars().count() > MAX_SUGGESTION_TERM_LENGTH {
+            return Ok(vec![]);
+        }
+        let distance = self.typo_distance(&q) as i32;
+
+        icons::Entity::find()
+            .select_only()
+            .column(icons::Column::Name)
+            .filter(icons::Column::Published.eq(true))
+            .filter(Expr::cust_with_values(
+                format!("{} >= $4", *SEARCH_SCORE),
+                [
+                    Value::from(vec![q.clone()]),
+                    Value::from(q.clone()),
+                    Value::from(distance),
+                    Value::from(self.effective_threshold(None)),
+                ],
+            ))
+            .order_by_desc(Expr::cust_with_values(
+                SEARCH_SCORE.as_str(),
+                [
+                    Value::from(vec![q.clone()]),
+                    Value::from(q),
+                    Value::from(distance),
+                ],
+            ))
+            .order_by_asc(icons::Column::Name)
+            .limit(limit)
+            .into_tuple::<String>()
+            .all(&self.conn)
+            .await
+    }
+
+    /// The similarity threshold for a search that asked for `requested`, or for the configured
+    /// default if it didn't, raised to the configured floor.
+    pub fn effective_threshold(&self, requested: Option<f64>) -> f64 {
+        requested
+            .unwrap_or(self.search_threshold)
+            .max(self.search_min_threshold)
+    }
+
+    /// The most typos to tolerate in the search term `q`. Terms get one edit per four characters,
+    /// so short terms don't match most of the catalog.
+    fn typo_distance(&self, q: &str) -> u32 {
+        let len = q.chars().count();
+        if len < MIN_TYPO_TERM_LENGTH {
+            0
+        } else {
+            self.search_max_distance.min((len / 4) as u32)
+        }
+    }
+
+    /// Published icons whose name or alias starts with the query's prefix, ordered by name.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn autocomplete(&self, query: &AutocompleteQuery) -> Result<Vec<IconName>, DbErr> {
+        let prefix = query.prefix.trim().to_lowercase();
+        if prefix.is_empty() {
+            return Ok(vec![]);
+        }
+        let pattern = format!(
+            "{}%",
+            prefix
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+
+        icons::Entity::find()
+            .select_only()
+            .column(icons::Column::Id)
+            .column(icons::Column::Name)
+            .filter(icons::Column::Published.eq(true))
+            .filter(
+                Condition::any()
+                    .add(icons::Column::Name.like(&pattern))
+                    .add(icons::Column::Alias.like(&pattern)),
+            )
+            .order_by_asc(icons::Column::Name)
+            .limit(query.limit(&self.page_size))
+            .into_model::<IconName>()
+            .all(&self.conn)
+            .await
+    }
+
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_deprecations(&self) -> Result<Vec<Deprecation>, DbErr> {
+        let deprecated = icons::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(icons::Column::DeprecatedAt.is_not_null())
+                    .add(icons::Column::Status.eq(IconStatus::Deprecated.to_string())),
+            )
+            .order_by(icons::Column::Name, Order::Asc)
+            .all(&self.conn)
+            .await?;
+        let replacements = self.get_replacements().await?;
+
+        Ok(deprecated
+            .into_iter()
+            .map(|icon| Deprecation {
+                replacement: resolve_replacement(&replacements, &icon.name),
+                name: icon.name,
+                deprecated_at: icon.deprecated_at,
+            })
+            .collect())
+    }
+
+    /// Icons with a deprecation version, most recently deprecated first, each with the name of
+    /// the icon that replaces it, if any.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_deprecated_icons(&self) -> Result<Vec<(icons::Model, Option<String>)>, DbErr> {
+        let deprecated = icons::Entity::find()
+            .filter(icons::Column::DeprecatedAt.is_not_null())
+            .order_by(icons::Column::DeprecatedAt, Order::Desc)
+            .order_by(icons::Column::Name, Order::Asc)
+            .all(&self.conn)
+            .await?;
+        let replacements = self.get_replacements().await?;
+
+        Ok(deprecated
+            .into_iter()
+            .map(|icon| {
+                let replacement = resolve_replacement(&replacements, &icon.name);
+                (icon, replacement)
+            })
+            .collect())
+    }
+
+    /// Map each former icon name to the name of the icon that took it as an alias.
+    async fn get_replacements(&self) -> Result<HashMap<String, String>, DbErr> {
+        let aliased = icons::Entity::find()
+            .filter(icons::Column::Alias.is_not_null())
+            .all(&self.conn)
+            .await?;
+        Ok(aliased
+            .into_iter()
+            .filter_map(|icon| icon.alias.map(|alias| (alias, icon.name)))
+            .collect())
+    }
+
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_all_tags(&self) -> Result<Vec<String>, DbErr> {
+        icons::Entity::find()
+            .select_only()
+            .column(icons::Column::Tags)
+            .into_tuple::<Vec<String>>()
+            .all(&self.conn)
+            .await
+            .map(|rows| rows.into_iter().flatten().collect::<Vec<_>>())
+    }
+
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_tag_counts(&self) -> Result<Vec<TagCount>, DbErr> {
+        let stmt = Statement::from_string(
+            self.conn.get_database_backend(),
+            "SELECT tag, COUNT(*) AS count \
+             FROM icons, unnest(tags) AS tag \
+             WHERE tag <> '' \
+             GROUP BY tag \
+             ORDER BY count DESC, tag ASC",
+        );
+        let rows = self.conn.query_all(stmt).await?;
+        rows.into_iter()
+            .map(|row| {
+                Ok(TagCount {
+                    tag: row.try_get("", "tag")?,
+                    count: row.try_get::<i64>("", "count")? as usize,
+                })
+            })
+            .collect()
+    }
+
+    /// Every category with the number of published icons in it, in [`Category::ALL`] order.
+    /// Categories without icons are included with a count of zero.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_category_counts(&self) -> Result<Vec<CategoryCount>, DbErr> {
+        let stmt = Statement::from_sql_and_values(
+            self.conn.get_database_backend(),
+            "SELECT search_category, COUNT(icons.id) AS count \
+             FROM unnest($1::text[]) AS search_category \
+             LEFT JOIN icons \
+               ON icons.published AND icons.search_categories && ARRAY[search_category] \
+             GROUP BY search_category",
+            [Category::ALL
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .into()],
+        );
+        let counts = self
+            .conn
+            .query_all(stmt)
+            .await?
+            .into_iter()
+            .map(|row| {
+                Ok((
+                    row.try_get::<String>("", "search_category")?,
+                    row.try_get::<i64>("", "count")? as u64,
+                ))
+            })
+            .collect::<Result<HashMap<_, _>, DbErr>>()?;
+
+        Ok(Category::ALL
+            .iter()
+            .map(|category| CategoryCount {
+                category: category.clone(),
+                count: counts
+                    .get(&category.to_string())
+                    .copied()
+                    .unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    /// The weights with a stored SVG for an icon, in [`IconWeight::ALL`] order. Only the weight
+    /// column is read, not the SVG source.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_icon_weights(&self, icon_id: i32) -> Result<Vec<IconWeight>, DbErr> {
+        let stored = svgs::Entity::find()
+            .select_only()
+            .column(svgs::Column::Weight)
+            .distinct()
+            .filter(svgs::Column::IconId.eq(icon_id))
+            .into_tuple::<String>()
+            .all(&self.conn)
+            .await?
+            .into_iter()
+            .filter_map(|weight| IconWeight::from_str(&weight).ok())
+            .collect::<HashSet<_>>();
+
+        Ok(IconWeight::ALL
+            .into_iter()
+            .filter(|weight| stored.contains(weight))
+            .collect())
+    }
+
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_svg_weights_by_icon_id(
+        &self,
+        icon_id: i32,
+    ) -> Result<HashMap<IconWeight, Svg>, DbErr> {
+        let svgs: Vec<svgs::Model> = svgs::Entity::find()
+            .filter(svgs::Column::IconId.eq(icon_id))
+            .all(&self.conn)
+            .await?;
+
+        Ok(svgs
+            .into_iter()
+            .filter_map(|s| match IconWeight::from_str(&s.weight) {
+                Ok(weight) => Some((weight, Svg::from(s))),
+                Err(e) => {
+                    tracing::warn!("Skipping SVG {} for icon {}: {}", s.id, icon_id, e);
+                    None
+                }
+            })
+            .collect::<HashMap<_, _>>())
+    }
+
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_svg_weights_by_icon_ids(
+        &self,
+        icon_ids: &[i32],
+    ) -> Result<HashMap<i32, HashMap<IconWeight, Svg>>, DbErr> {
+        let svgs: Vec<svgs::Model> = svgs::Entity::find()
+            .filter(svgs::Column::IconId.is_in(icon_ids.iter().copied()))
+            .all(&self.conn)
+            .await?;
+
+        let mut by_icon = HashMap::<i32, HashMap<IconWeight, Svg>>::new();
+        for s in svgs {
+            match IconWeight::from_str(&s.weight) {
+                Ok(weight) => {
+                    by_icon
+                        .entry(s.icon_id)
+                        .or_default()
+                        .insert(weight, Svg::from(s));
+                }
+                Err(e) => tracing::warn!("Skipping SVG {} for icon {}: {}", s.id, s.icon_id, e),
+            }
+        }
+        Ok(by_icon)
+    }
+
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn upsert_svg(&self, svg: svgs::Model) -> Result<i32, DbErr> {
+        let active_model: svgs::ActiveModel = svg.into();
+        let res = svgs::Entity::insert(active_model)
+            .on_conflict(
+                OnConflict::columns(vec![svgs::Column::IconId, svgs::Column::Weight])
+                    .update_column(svgs::Column::Src)
+                    .to_owned(),
+            )
+            .exec(&self.conn)
+            .await?;
+        Ok(res.last_insert_id)
+    }
+
+    /// Insert or update many SVGs in a single statement, keyed on icon and weight as in
+    /// [`Db::upsert_svg`]. The batch is applied in a transaction, so either every SVG is stored or
+    /// none are. Returns the number of rows written.
+    #[tracing::instrument(level = "info", skip_all, fields(count = svgs.len()))]
+    pub async fn upsert_svgs(&self, svgs: Vec<svgs::Model>) -> Result<u64, DbErr> {
+        // Postgres refuses to update the same row twice in one statement, so only the last SVG for
+        // each icon and weight is kept.
+        let svgs = svgs
+            .into_iter()
+            .map(|svg| ((svg.icon_id, svg.weight.clone()), svg))
+            .collect::<HashMap<_, _>>();
+        if svgs.is_empty() {
+            return Ok(0);
+        }
+
+        let active_models = svgs.into_values().map(|svg| {
+            let mut active_model: svgs::ActiveModel = svg.into();
+            active_model.id = ActiveValue::NotSet;
+            active_model
+        });
+        let txn = self.conn.begin().await?;
+        let rows = svgs::Entity::insert_many(active_models)
+            .on_conflict(
+                OnConflict::columns(vec![svgs::Column::IconId, svgs::Column::Weight])
+                    .update_column(svgs::Column::Src)
+                    .to_owned(),
+            )
+            .exec_without_returning(&txn)
+            .await?;
+        txn.commit().await?;
+        Ok(rows)
+    }
+
+    /// Icons released no later than the `current_version` override, along with unreleased ones.
+    /// Matches every icon when no override is configured.
+    fn released_by_current_version(&self) -> Condition {
+        match self.current_version {
+            Some(version) => Condition::any()
+                .add(icons::Column::ReleasedAt.is_null())
+                .add(icons::Column::ReleasedAt.lte(version)),
+            None => Condition::all(),
+        }
+    }
+
+    /// Narrow `range` to versions no later than the `current_version` override, if one is
+    /// configured, so that changes made after it aren't reported.
+    pub fn until_current_version(&self, range: IconReleaseQuery) -> IconReleaseQuery {
+        match self.current_version {
+            Some(version) => range.until(version),
+            None => range,
+        }
+    }
+
+    /// Describe the current library version and published icon count. When a
+    /// `current_version` override is configured, icons released after it are not counted and the
+    /// override is reported as the version.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn get_library_info(&self) -> Result<LibraryInfo, DbErr> {
+        let cond = Condition::all()
+            .add(icons::Column::Published.eq(true))
+            .add(self.released_by_current_version());
+
+        icons::Entity::find()
+            .select_only()
+            .column_as(Expr::col(icons::Column::Id).count(), "count")
+            .column_as(Expr::col(icons::Column::ReleasedAt).max(), "version")
+            .filter(cond)
+            // Postgres counts are BIGINT, which sqlx cannot decode into `u64` directly.
+            .into_tuple::<(i64, Option<f64>)>()
+            .one(&self.conn)
+            .await
+            .map(|row| {
+                let (count, version) = row.unwrap_or_default();
+                LibraryInfo {
+                    count: count as u64,
+                    version: self.current_version.or(version).unwrap_or_default(),
+                }
+            })
+    }
+}
+
+/// Follow the alias chain from `name` to the icon that ultimately replaces it, in case the
+/// replacement was itself renamed later on.
+fn resolve_replacement(replacements: &HashMap<String, String>, name: &str) -> Option<String> {
+    let mut seen = HashSet::from([name.to_string()]);
+    let mut replacement = None;
+    let mut current = name;
+    while let Some(next) = replacements.get(current) {
+        if !seen.insert(next.clone()) {
+            break;
+        }
+        replacement = Some(next.clone());
+        current = next;
+    }
+    replacement
+}
+
+/// An icon row along with its relevance to a search term.
+#[derive(Debug)]
+pub struct ScoredModel {
+    pub icon: icons::Model,
+    pub score: f64,
+}
+
+impl FromQueryResult for ScoredModel {
+    fn from_query_result(res: &QueryResult, pre: &str) -> Result<Self, DbErr> {
+        Ok(Self {
+            icon: icons::Model::from_query_result(res, pre)?,
+            score: res.try_get(pre, "score")?,
+        })
+    }
+}
+
+/// A snapshot of what is loaded in the database, for monitoring.
+#[derive(Debug, Serialize)]
+pub struct DbStats {
+    /// Total icons, published or not.
+    pub icons: u64,
+    pub published: u64,
+    /// Total SVG rows across all weights.
+    pub svgs: u64,
+    pub by_status: BTreeMap<String, u64>,
+    /// Icons per search category. Icons in several categories are counted in each.
+    pub by_category: BTreeMap<String, u64>,
+    pub weights: WeightSummary,
+    /// The similarity threshold applied to searches that don't specify one.
+    pub search_threshold: f64,
+    /// The lowest similarity threshold a search may ask for.
+    pub search_min_threshold: f64,
+}
+
+/// SVG coverage across published icons.
+#[derive(Debug, Serialize)]
+pub struct WeightSummary {
+    /// Icons with an SVG in every weight.
+    pub complete: u64,
+    /// Icons with no SVG in each weight.
+    pub missing: BTreeMap<String, u64>,
+}
+
+/// The state of the schema and data, as reported by [`Db::readiness`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Readiness {
+    /// Whether every table the server queries exists.
+    pub tables_present: bool,
+    /// The number of icons in the database, or `None` if the tables are missing.
+    pub icon_count: Option<u64>,
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.tables_present && self.icon_count.is_some_and(|count| count > 0)
+    }
+}
+
+/// What [`Db::upsert_icon`] did with an icon.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpsertOutcome {
+    Inserted,
+    Updated,
+    Unchanged,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query, style = Form)]
+pub struct IconSearch {
+    /// A fuzzy search term to match against icon names, aliases, tags, and othjer properties. At
+    /// most 128 characters.
+    #[serde(alias = "query")]
+    #[param(example = "block")]
+    pub q: String,
+    /// When `true`, each result includes the field and character ranges that matched, for
+    /// emphasizing the match in a UI.
+    #[param(example = true)]
+    pub highlight: Option<bool>,
+    /// The maximum number of results to return. Defaults to 100, and is capped at 500 unless the
+    /// server is configured otherwise.
+    #[param(example = 100)]
+    pub limit: Option<u64>,
+    /// The number of results to skip, for use with `limit`.
+    #[param(example = 0)]
+    pub offset: Option<u64>,
+    /// The minimum similarity, from 0 to 1, for a result to be returned. Higher values trade
+    /// recall for precision. Defaults to the server's configured threshold, and is raised to the
+    /// server's minimum if lower.
+    #[param(example = 0.3)]
+    pub threshold: Option<f64>,
+}
+
+impl IconSearch {
+    pub fn validate(&self) -> Result<(), String> {
+        let length = self.q.trim().chars().count();
+        if length > MAX_SEARCH_TERM_LENGTH {
+            return Err(format!(
+                "Search term must be at most {MAX_SEARCH_TERM_LENGTH} characters, got {length}"
+            ));
+        }
+        match self.threshold {
+            Some(threshold) if !(0.0..=1.0).contains(&threshold) => Err(format!(
+                "Search threshold must be from 0 to 1, got {threshold}"
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query, style = Form)]
+pub struct AutocompleteQuery {
+    /// The start of an icon name or alias.
+    #[param(example = "cu")]
+    pub prefix: String,
+    /// The maximum number of suggestions to return. Defaults to 10, and is clamped to between 1
+    /// and 50, or the server's maximum page size if that is lower.
+    #[param(example = 10)]
+    pub limit: Option<u64>,
+}
+
+impl AutocompleteQuery {
+    pub const PAGE_SIZE: PageSize = PageSize {
+        default: 10,
+        max: 50,
+    };
+
+    pub fn limit(&self, page_size: &PageSize) -> u64 {
+        Self::PAGE_SIZE.capped_by(page_size).clamp_limit(self.limit)
+    }
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query, style = Form)]
+pub struct DiffQuery {
+    /// The earlier `<major>.<minor>` version, inclusive.
+    #[param(example = "2.0")]
+    pub from: String,
+    /// The later `<major>.<minor>` version, inclusive.
+    #[param(example = "2.1")]
+    pub to: String,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query, style = Form)]
+pub struct LatestQuery {
+    /// The maximum number of icons to return. Defaults to 20, and is clamped to between 1 and
+    /// 100, or the server's maximum page size if that is lower.
+    #[param(example = 20)]
+    pub limit: Option<u64>,
+}
+
+impl LatestQuery {
+    pub const PAGE_SIZE: PageSize = PageSize {
+        default: 20,
+        max: 100,
+    };
+
+    pub fn limit(&self, page_size: &PageSize) -> u64 {
+        Self::PAGE_SIZE.capped_by(page_size).clamp_limit(self.limit)
+    }
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IconBatch {
+    /// The IDs of the icons to fetch, at most 200.
+    #[schema(example = json!([2884, 3001]))]
+    pub ids: Vec<i32>,
+}
+
+impl IconBatch {
+    pub const MAX_IDS: usize = 200;
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query, style = Form)]
+pub struct SvgQuery {
+    /// A hex (`#ff0000`) or named (`red`) CSS color to replace `currentColor` with. Remember to
+    /// URL-encode the `#`.
+    #[param(example = "#ff0000")]
+    pub color: Option<String>,
+    /// Serve a placeholder question-mark box with `X-Icon-Fallback: true` instead of a 404 when
+    /// the icon or weight is missing. Defaults to `false`.
+    #[param(example = true)]
+    pub fallback: Option<bool>,
+    /// When `false`, a missing weight is substituted with regular, or failing that any weight the
+    /// icon has, and the weight served is given in the `X-Icon-Weight-Served` header. Substituted
+    /// weights aren't cached. Defaults to `true`.
+    #[param(example = false)]
+    pub strict: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query, style = Form)]
+pub struct RandomQuery {
+    /// The number of icons to return. Defaults to 1, and is clamped to between 1 and 50, or the
+    /// server's maximum page size if that is lower.
+    #[param(example = 3)]
+    pub count: Option<u64>,
+}
+
+impl RandomQuery {
+    pub const PAGE_SIZE: PageSize = PageSize {
+        default: 1,
+        max: 50,
+    };
+
+    pub fn count(&self, page_size: &PageSize) -> u64 {
+        Self::PAGE_SIZE.capped_by(page_size).clamp_limit(self.count)
+    }
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query, style = Form)]
+pub struct FormatQuery {
+    /// The response format. `csv` returns every matching icon (up to 500) as a CSV download, and
+    /// can also be requested with `Accept: text/csv`. Defaults to `json`.
+    #[param(example = "csv")]
+    pub format: Option<ExportFormat>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query, style = Form)]
+pub struct WeightQuery {
+    /// The icon weight to include. Defaults to `regular`.
+    #[param(example = "bold")]
+    pub weight: Option<IconWeight>,
+}
+
+/// How SVG source is represented in icon responses.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SvgEncoding {
+    /// The SVG markup itself.
+    #[default]
+    Raw,
+    /// A base64 `data:image/svg+xml` URI, ready to embed in `src` or `url()`.
+    DataUri,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query, style = Form)]
+pub struct EncodingQuery {
+    /// How to encode SVG source. Defaults to `raw`.
+    #[param(example = "datauri")]
+    pub encode: Option<SvgEncoding>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query, style = Form)]
+pub struct DownloadQuery {
+    /// Only include this weight. Defaults to every weight.
+    #[param(example = "bold")]
+    pub weight: Option<IconWeight>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query, style = Form)]
+pub struct RenderQuery {
+    /// The width and height of the rendered image in pixels. Defaults to 256, and is capped at
+    /// 1024.
+    #[param(example = 256)]
+    pub size: Option<u32>,
+    /// When `false`, a missing weight is substituted with regular, or failing that any weight the
+    /// icon has, and the weight served is given in the `X-Icon-Weight-Served` header. Substituted
+    /// weights aren't cached. Defaults to `true`.
+    #[param(example = false)]
+    pub strict: Option<bool>,
+}
+
+impl RenderQuery {
+    pub const DEFAULT_SIZE: u32 = 256;
+    pub const MAX_SIZE: u32 = 1024;
 
-    #[tracing::instrument(level = "info", skip(self))]
-    pub async fn upsert_icon(&self, icon: icons::Model) -> Result<i32, DbErr> {
-        let active_model: icons::ActiveModel = icon.into();
-        let res = icons::Entity::insert(active_model)
-            .on_conflict(
-                OnConflict::column(icons::Column::Rid)
-                    .update_column(icons::Column::Name)
-                    .update_column(icons::Column::Status)
-                    .update_column(icons::Column::Category)
-                    .update_column(icons::Column::SearchCategories)
-                    .update_column(icons::Column::Tags)
-                    .update_column(icons::Column::Notes)
-                    .update_column(icons::Column::ReleasedAt)
-                    .update_column(icons::Column::LastUpdatedAt)
-                    .update_column(icons::Column::DeprecatedAt)
-                    .update_column(icons::Column::Published)
-                    .update_column(icons::Column::Alias)
-                    .update_column(icons::Column::Code)
-                    .to_owned(),
-            )
-            .exec(&self.conn)
-            .await?;
-        Ok(res.last_insert_id)
+    pub fn size(&self) -> Result<u32, String> {
+        match self.size {
+            Some(0) => Err("Image size must be greater than 0".to_string()),
+            size => Ok(size.unwrap_or(Self::DEFAULT_SIZE).min(Self::MAX_SIZE)),
+        }
     }
+}
 
-    #[tracing::instrument(level = "info", skip(self))]
-    pub async fn delete_icon(&self, rid: &str) -> Result<u64, DbErr> {
-        icons::Entity::delete_many()
-            .filter(icons::Column::Rid.eq(rid))
-            .exec(&self.conn)
-            .await
-            .map(|res| res.rows_affected)
-    }
+/// An output format held in the render cache.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderFormat {
+    Svg,
+    Png,
+}
 
-    #[tracing::instrument(level = "info", skip(self))]
-    pub async fn query_icons(&self, query: &IconSearch) -> Result<Vec<icons::Model>, DbErr> {
-        // TODO: Implement fuzzy search and relevance scoring
-        todo!("Implement query_icons with fuzzy search and relevance");
-    }
+impl FromStr for RenderFormat {
+    type Err = String;
 
-    #[tracing::instrument(level = "info", skip(self))]
-    pub async fn get_all_tags(&self) -> Result<Vec<String>, DbErr> {
-        icons::Entity::find()
-            .select_only()
-            .column(icons::Column::Tags)
-            .all(&self.conn)
-            .await
-            .map(|models| {
-                models
-                    .into_iter()
-                    .flat_map(|model| model.tags)
-                    .collect::<Vec<_>>()
-            })
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "svg" => Ok(RenderFormat::Svg),
+            "png" => Ok(RenderFormat::Png),
+            _ => Err(format!("Invalid render format: {s}")),
+        }
     }
+}
 
-    #[tracing::instrument(level = "info", skip(self))]
-    pub async fn get_icon_weights_by_icon_id(
-        &self,
-        icon_id: i32,
-    ) -> Result<HashMap<String, svgs::Model>, DbErr> {
-        let svgs: Vec<svgs::Model> = svgs::Entity::find()
-            .filter(svgs::Column::IconId.eq(icon_id))
-            .all(&self.conn)
-            .await?;
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query, style = Form)]
+pub struct PrimeQuery {
+    /// One or more comma-separated formats to render. Defaults to `svg,png`.
+    #[serde(default, deserialize_with = "deserialize_csv")]
+    #[param(value_type = Option<String>, explode = false, example = "svg,png")]
+    pub formats: Option<Vec<RenderFormat>>,
+    /// The size of rendered PNGs in pixels. Defaults to 256, and is capped at 1024.
+    #[param(example = 256)]
+    pub size: Option<u32>,
+}
 
-        Ok(svgs
-            .into_iter()
-            .map(|s| (s.weight.clone(), s))
-            .collect::<HashMap<_, _>>())
+impl PrimeQuery {
+    pub fn formats(&self) -> Vec<RenderFormat> {
+        match &self.formats {
+            Some(formats) if !formats.is_empty() => formats.clone(),
+            _ => vec![RenderFormat::Svg, RenderFormat::Png],
+        }
     }
 
-    #[tracing::instrument(level = "info", skip(self))]
-    pub async fn upsert_svg(&self, svg: svgs::Model) -> Result<i32, DbErr> {
-        let active_model: svgs::ActiveModel = svg.into();
-        let res = svgs::Entity::insert(active_model)
-            .on_conflict(
-                OnConflict::columns(vec![svgs::Column::IconId, svgs::Column::Weight])
-                    .update_column(svgs::Column::Src)
-                    .to_owned(),
-            )
-            .exec(&self.conn)
-            .await?;
-        Ok(res.last_insert_id)
+    pub fn size(&self) -> Result<u32, String> {
+        RenderQuery {
+            size: self.size,
+            ..Default::default()
+        }
+        .size()
     }
+}
 
-    #[tracing::instrument(level = "info", skip(self))]
-    pub async fn get_library_info(&self) -> Result<LibraryInfo, DbErr> {
-        icons::Entity::find()
-            .select_only()
-            .column_as(Expr::col(icons::Column::Id).count(), "count")
-            .column_as(Expr::col(icons::Column::ReleasedAt).max(), "version")
-            .filter(icons::Column::Published.eq(true))
-            .into_model::<LibraryInfo>()
-            .one(&self.conn)
-            .await
-            .map(|opt| {
-                opt.unwrap_or_else(|| LibraryInfo {
-                    count: 0,
-                    version: 0.0,
-                })
-            })
-    }
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query, style = Form)]
+pub struct CategoriesQuery {
+    /// When `true`, return each category with the number of published icons in it.
+    #[param(example = true)]
+    pub with_counts: Option<bool>,
 }
 
 #[derive(Debug, Default, Deserialize, IntoParams)]
 #[into_params(parameter_in = Query, style = Form)]
-pub struct IconSearch {
-    /// A fuzzy search term to match against icon names, aliases, tags, and othjer properties.
-    #[serde(alias = "query")]
-    #[param(example = "block")]
-    pub q: String,
+pub struct TagsQuery {
+    /// When `true`, return each tag with the number of icons carrying it instead of bare tag names.
+    #[param(example = true)]
+    pub with_counts: Option<bool>,
+}
+
+/// A partial icon update. Fields that are absent are left unchanged, while nullable fields may be
+/// explicitly set to `null` to clear them.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct IconPatch {
+    #[schema(example = "cube")]
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub alias: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub code: Option<Option<i32>>,
+    pub status: Option<IconStatus>,
+    pub search_categories: Option<Vec<Category>>,
+    pub category: Option<FigmaCategory>,
+    #[schema(example = json!(["square", "box", "3d"]))]
+    pub tags: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub notes: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub released_at: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub last_updated_at: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub deprecated_at: Option<Option<f64>>,
+    pub published: Option<bool>,
+}
+
+/// Distinguishes an explicit `null` (`Some(None)`) from an absent field (`None`).
+fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
 }
 
 #[derive(Debug, Default, Deserialize, IntoParams)]
@@ -334,6 +1614,12 @@ pub struct IconQuery {
     pub updated: Option<IconReleaseQuery>,
     #[serde(skip)]
     pub deprecated: Option<IconReleaseQuery>,
+    /// Filter search results by font codepoint or codepoint range, using the same syntax as `v`.
+    /// Codepoints may be decimal or `0x`-prefixed hexadecimal. Icons without a codepoint never
+    /// match.
+    #[serde(default, deserialize_with = "deserialize_optional_code_query")]
+    #[param(example = "0xe000..0xf8ff")]
+    pub code: Option<CodeQuery>,
     /// Filter search results by one or more comma-separated release statuses.
     #[serde(default, deserialize_with = "deserialize_csv")]
     #[param(explode = false)]
@@ -346,8 +1632,41 @@ pub struct IconQuery {
     #[serde(default, deserialize_with = "deserialize_csv")]
     #[param(explode = false)]
     pub tags: Option<Vec<String>>,
-    pub order: Option<OrderColumn>,
+    /// Exclude icons in any of one or more comma-separated icon categories.
+    #[serde(default, deserialize_with = "deserialize_csv")]
+    #[param(explode = false)]
+    pub exclude_category: Option<Vec<Category>>,
+    /// Exclude icons carrying any of one or more comma-separated tags.
+    #[serde(default, deserialize_with = "deserialize_csv")]
+    #[param(explode = false)]
+    pub exclude_tags: Option<Vec<String>>,
+    /// Only include icons with a stored SVG in this weight.
+    #[param(example = "duotone")]
+    pub has_weight: Option<IconWeight>,
+    /// Filter search results by whether the icon has design or usage notes.
+    #[param(example = true)]
+    pub has_notes: Option<bool>,
+    /// Sort results by one or more comma-separated fields (`name`, `status`, `release`, `code`,
+    /// or `updated`), each optionally suffixed with `:asc` or `:desc`. Later fields break ties in
+    /// earlier ones. Icons missing a sort field always come last.
+    #[serde(default, deserialize_with = "deserialize_csv")]
+    #[param(value_type = Option<String>, explode = false, example = "updated:desc,name")]
+    pub order: Option<Vec<OrderField>>,
+    /// The default sort direction for `order` fields without an explicit direction.
     pub dir: Option<OrderDirection>,
+    /// The maximum number of icons to return. Defaults to 100; values above 500 are clamped,
+    /// unless the server is configured otherwise.
+    #[param(example = 100)]
+    pub limit: Option<u64>,
+    /// The number of icons to skip before returning results, for use with `limit`.
+    #[param(example = 0)]
+    pub offset: Option<u64>,
+    /// Resume listing after the last icon of a previous page, using the `next_cursor` it
+    /// returned. Unlike `offset`, cursors are unaffected by icons being added or removed between
+    /// requests. The other parameters must match the request that produced the cursor.
+    #[serde(default, deserialize_with = "deserialize_optional_cursor")]
+    #[param(value_type = Option<String>)]
+    pub cursor: Option<Cursor>,
 }
 
 impl IconQuery {
@@ -355,6 +1674,41 @@ impl IconQuery {
         IconQuery::default().published(Ternary::True)
     }
 
+    /// Apply the default page size when no `limit` was given, and clamp it to the maximum.
+    pub fn paginated(mut self, page_size: &PageSize) -> Self {
+        self.limit = Some(page_size.clamp_limit(self.limit));
+        self
+    }
+
+    /// Like [`IconQuery::paginated`], but for bulk exports, which include every match unless a
+    /// `limit` was given. A given `limit` is clamped as for a page.
+    pub fn bulk(mut self, page_size: &PageSize) -> Self {
+        self.limit = self.limit.map(|limit| page_size.clamp_limit(Some(limit)));
+        self
+    }
+
+    /// Whether results may leave out some matches, because a `limit`, `offset`, or `cursor` was
+    /// given.
+    pub fn is_partial(&self) -> bool {
+        self.limit.is_some() || self.offset.is_some() || self.cursor.is_some()
+    }
+
+    /// Whether this is a plain listing of published icons in the default order, with at most
+    /// `limit` and `offset` set.
+    pub fn is_default_listing(&self) -> bool {
+        !self.has_clauses() && self.order.is_none() && self.dir.is_none() && self.cursor.is_none()
+    }
+
+    /// Check that `cursor`, if present, was produced under the same sort as this query.
+    pub fn validate_cursor(&self) -> Result<(), String> {
+        match &self.cursor {
+            Some(cursor) if cursor.order != order_signature(&Db::build_order_from_params(self)) => {
+                Err("Cursor does not match the requested order".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub fn name(mut self, name: String) -> Self {
         self.name = Some(name);
         self
@@ -404,6 +1758,11 @@ impl IconQuery {
             || self.released.is_some()
             || self.updated.is_some()
             || self.deprecated.is_some()
+            || self.code.is_some()
+            || self.exclude_category.is_some()
+            || self.exclude_tags.is_some()
+            || self.has_weight.is_some()
+            || self.has_notes.is_some()
     }
 }
 
@@ -475,6 +1834,59 @@ impl FromStr for IconReleaseQuery {
     }
 }
 
+impl IconReleaseQuery {
+    /// Parse a single `<major>.<minor>` version into a query that matches exactly that version,
+    /// tolerating the rounding error inherent in storing versions as floats.
+    pub fn version(s: &str) -> Result<Self, String> {
+        let v = Self::parse_version(s)?;
+        Ok(IconReleaseQuery::Range(
+            v - Self::VERSION_EPSILON,
+            v + Self::VERSION_EPSILON,
+        ))
+    }
+
+    /// Parse two `<major>.<minor>` versions into a query matching every version from `from` to
+    /// `to`, inclusive.
+    pub fn between(from: &str, to: &str) -> Result<Self, String> {
+        let (from_v, to_v) = (Self::parse_version(from)?, Self::parse_version(to)?);
+        if from_v > to_v {
+            return Err(format!("Version {from} is after {to}"));
+        }
+        Ok(IconReleaseQuery::Range(
+            from_v - Self::VERSION_EPSILON,
+            to_v + Self::VERSION_EPSILON,
+        ))
+    }
+
+    /// Narrow the query to versions no later than `version`. A query entirely after `version`
+    /// becomes an empty range.
+    pub fn until(self, version: f64) -> Self {
+        let until = version + Self::VERSION_EPSILON;
+        match self {
+            IconReleaseQuery::Exact(v) => IconReleaseQuery::Range(v, v.min(until)),
+            IconReleaseQuery::Range(a, b) => IconReleaseQuery::Range(a, b.min(until)),
+            IconReleaseQuery::LessThanOrEqual(v) => IconReleaseQuery::LessThanOrEqual(v.min(until)),
+            IconReleaseQuery::GraterThanOrEqual(v) => IconReleaseQuery::Range(v, until),
+        }
+    }
+
+    const VERSION_EPSILON: f64 = 1e-6;
+
+    fn parse_version(s: &str) -> Result<f64, String> {
+        let valid = s.split_once('.').is_some_and(|(major, minor)| {
+            [major, minor]
+                .iter()
+                .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+        });
+        if !valid {
+            return Err(format!("Invalid version: {s}"));
+        }
+
+        s.parse::<f64>()
+            .map_err(|e| format!("Invalid number: {}", e))
+    }
+}
+
 fn deserialize_optional_icon_release_query<'de, D>(
     deserializer: D,
 ) -> Result<Option<IconReleaseQuery>, D::Error>
@@ -490,6 +1902,51 @@ where
     }
 }
 
+#[derive(Debug, Clone, ToSchema)]
+pub enum CodeQuery {
+    Exact(i32),
+    Range(i32, i32),
+    LessThanOrEqual(i32),
+    GreaterThanOrEqual(i32),
+}
+
+fn parse_codepoint(s: &str) -> Result<i32, String> {
+    let res = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => i32::from_str_radix(hex, 16),
+        None => s.parse::<i32>(),
+    };
+    res.map_err(|e| format!("Invalid codepoint {s:?}: {e}"))
+}
+
+impl FromStr for CodeQuery {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((a, b)) = s.split_once("..") {
+            match (a.trim(), b.trim()) {
+                ("", b) => Ok(CodeQuery::LessThanOrEqual(parse_codepoint(b)?)),
+                (a, "") => Ok(CodeQuery::GreaterThanOrEqual(parse_codepoint(a)?)),
+                (a, b) => Ok(CodeQuery::Range(parse_codepoint(a)?, parse_codepoint(b)?)),
+            }
+        } else {
+            Ok(CodeQuery::Exact(parse_codepoint(s.trim())?))
+        }
+    }
+}
+
+fn deserialize_optional_code_query<'de, D>(deserializer: D) -> Result<Option<CodeQuery>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let opt = Option::<String>::deserialize(deserializer)?;
+    match opt {
+        Some(s) => CodeQuery::from_str(&s)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum OrderColumn {
@@ -498,6 +1955,102 @@ pub enum OrderColumn {
     Status,
     Release,
     Code,
+    /// The version in which the icon was last updated. Icons never updated sort last.
+    Updated,
+}
+
+/// An opaque position in a sorted icon listing: the sort key of the last icon on a page, and the
+/// sort it was taken under.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cursor {
+    order: String,
+    key: Vec<serde_json::Value>,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        URL_SAFE_NO_PAD
+            .decode(s)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| format!("Invalid cursor: {s}"))
+    }
+}
+
+fn deserialize_optional_cursor<'de, D>(deserializer: D) -> Result<Option<Cursor>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let opt = Option::<String>::deserialize(deserializer)?;
+    match opt {
+        Some(s) => Cursor::from_str(&s)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+fn order_signature(order: &[(icons::Column, Order)]) -> String {
+    order
+        .iter()
+        .map(|(column, dir)| format!("{}:{:?}", column.as_str(), dir))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn sort_key_value(model: &icons::Model, column: icons::Column) -> serde_json::Value {
+    match column {
+        icons::Column::Name => model.name.clone().into(),
+        icons::Column::Status => model.status.clone().into(),
+        icons::Column::ReleasedAt => model.released_at.into(),
+        icons::Column::LastUpdatedAt => model.last_updated_at.into(),
+        icons::Column::Code => model.code.into(),
+        _ => model.id.into(),
+    }
+}
+
+/// Convert a cursor's sort key value back into a SQL value for `column`, or `None` for NULL.
+fn cursor_value(column: icons::Column, value: &serde_json::Value) -> Option<Value> {
+    match column {
+        icons::Column::Name | icons::Column::Status => value.as_str().map(Value::from),
+        icons::Column::ReleasedAt | icons::Column::LastUpdatedAt => value.as_f64().map(Value::from),
+        _ => value.as_i64().map(|v| Value::from(v as i32)),
+    }
+}
+
+/// A single field of a multi-field sort, written `column` or `column:dir`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OrderField {
+    pub column: OrderColumn,
+    pub dir: Option<OrderDirection>,
+}
+
+impl FromStr for OrderField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (column, dir) = match s.split_once(':') {
+            Some((column, dir)) => (column, Some(dir)),
+            None => (s, None),
+        };
+        let column = serde_plain::from_str::<OrderColumn>(column.trim())
+            .map_err(|_| format!("Invalid order column: {column}"))?;
+        let dir = dir
+            .map(|dir| {
+                serde_plain::from_str::<OrderDirection>(dir.trim())
+                    .map_err(|_| format!("Invalid order direction: {dir}"))
+            })
+            .transpose()?;
+        Ok(OrderField { column, dir })
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy, Deserialize, ToSchema)]
@@ -525,3 +2078,210 @@ pub enum Ternary {
     False,
     Any,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db() -> Db {
+        Db {
+            conn: DatabaseConnection::Disconnected,
+            current_version: None,
+            search_threshold: 0.2,
+            search_min_threshold: 0.1,
+            search_max_distance: 2,
+            page_size: PageSize::default(),
+        }
+    }
+
+    #[test]
+    fn order_accepts_one_or_several_fields() {
+        let query = serde_qs::from_str::<IconQuery>("order=release").unwrap();
+        let order = query.order.unwrap();
+        assert_eq!(order.len(), 1);
+        assert!(matches!(
+            order[0],
+            OrderField {
+                column: OrderColumn::Release,
+                dir: None
+            }
+        ));
+
+        let query = serde_qs::from_str::<IconQuery>("order=status,name:desc").unwrap();
+        let order = query.order.unwrap();
+        assert_eq!(order.len(), 2);
+        assert!(matches!(
+            order[0],
+            OrderField {
+                column: OrderColumn::Status,
+                dir: None
+            }
+        ));
+        assert!(matches!(
+            order[1],
+            OrderField {
+                column: OrderColumn::Name,
+                dir: Some(OrderDirection::Desc)
+            }
+        ));
+
+        assert!(serde_qs::from_str::<IconQuery>("order=status,size").is_err());
+        assert!(serde_qs::from_str::<IconQuery>("order=name:sideways").is_err());
+    }
+
+    #[test]
+    fn page_sizes_are_clamped_at_the_boundaries() {
+        let page_size = PageSize::default();
+        assert_clamped(
+            |limit| page_size.clamp_limit(limit),
+            page_size.default,
+            page_size.max,
+        );
+        assert_eq!(page_size.clamp_limit(Some(u64::MAX)), page_size.max);
+    }
+
+    fn assert_clamped(limit: impl Fn(Option<u64>) -> u64, default: u64, max: u64) {
+        assert_eq!(limit(None), default);
+        assert_eq!(limit(Some(0)), 1);
+        assert_eq!(limit(Some(1)), 1);
+        assert_eq!(limit(Some(max)), max);
+        assert_eq!(limit(Some(max + 1)), max);
+    }
+
+    #[test]
+    fn endpoint_limits_are_clamped_at_the_boundaries() {
+        let page_size = PageSize::default();
+        assert_clamped(
+            |limit| {
+                AutocompleteQuery {
+                    prefix: String::new(),
+                    limit,
+                }
+                .limit(&page_size)
+            },
+            10,
+            50,
+        );
+        assert_clamped(|limit| LatestQuery { limit }.limit(&page_size), 20, 100);
+        assert_clamped(|count| RandomQuery { count }.count(&page_size), 1, 50);
+    }
+
+    #[test]
+    fn endpoint_limits_are_capped_by_the_configured_max() {
+        let page_size = PageSize {
+            default: 5,
+            max: 30,
+        };
+        assert_clamped(|limit| LatestQuery { limit }.limit(&page_size), 20, 30);
+        let page_size = PageSize { default: 5, max: 8 };
+        assert_clamped(|limit| LatestQuery { limit }.limit(&page_size), 8, 8);
+        assert_clamped(|count| RandomQuery { count }.count(&page_size), 1, 8);
+    }
+
+    #[test]
+    fn replacements_follow_chained_aliases() {
+        let replacements = HashMap::from([
+            ("instagram-logo".to_string(), "instagram".to_string()),
+            ("instagram".to_string(), "instagram-glyph".to_string()),
+            ("loop-a".to_string(), "loop-b".to_string()),
+            ("loop-b".to_string(), "loop-a".to_string()),
+        ]);
+        assert_eq!(
+            resolve_replacement(&replacements, "instagram-logo").as_deref(),
+            Some("instagram-glyph")
+        );
+        assert_eq!(
+            resolve_replacement(&replacements, "instagram").as_deref(),
+            Some("instagram-glyph")
+        );
+        assert_eq!(resolve_replacement(&replacements, "cube"), None);
+        // A cycle stops before revisiting the starting name.
+        assert_eq!(
+            resolve_replacement(&replacements, "loop-a").as_deref(),
+            Some("loop-b")
+        );
+    }
+
+    #[test]
+    fn search_threshold_must_be_a_fraction() {
+        let search = |threshold| IconSearch {
+            q: "cube".to_string(),
+            threshold,
+            ..Default::default()
+        };
+        assert!(search(None).validate().is_ok());
+        assert!(search(Some(0.0)).validate().is_ok());
+        assert!(search(Some(1.0)).validate().is_ok());
+        assert!(search(Some(-0.1)).validate().is_err());
+        assert!(search(Some(1.5)).validate().is_err());
+        assert!(search(Some(f64::NAN)).validate().is_err());
+    }
+
+    #[test]
+    fn search_terms_are_limited_in_length() {
+        let search = |q: String| IconSearch {
+            q,
+            ..Default::default()
+        };
+        assert!(search("a".repeat(MAX_SEARCH_TERM_LENGTH))
+            .validate()
+            .is_ok());
+        assert!(
+            search(format!("  {}  ", "a".repeat(MAX_SEARCH_TERM_LENGTH)))
+                .validate()
+                .is_ok()
+        );
+        assert!(search("a".repeat(MAX_SEARCH_TERM_LENGTH + 1))
+            .validate()
+            .is_err());
+        assert!(search("é".repeat(300)).validate().is_err());
+    }
+
+    #[test]
+    fn bulk_queries_include_every_match_by_default() {
+        let page_size = PageSize {
+            default: 50,
+            max: 500,
+        };
+        let query = IconQuery::new().bulk(&page_size);
+        assert_eq!(query.limit, None);
+        assert!(!query.is_partial());
+
+        let query = IconQuery {
+            limit: Some(10_000),
+            ..IconQuery::new()
+        }
+        .bulk(&page_size);
+        assert_eq!(query.limit, Some(500));
+        assert!(query.is_partial());
+
+        let query = IconQuery {
+            offset: Some(20),
+            ..IconQuery::new()
+        }
+        .bulk(&page_size);
+        assert!(query.is_partial());
+    }
+
+    #[test]
+    fn release_queries_are_narrowed_to_a_version() {
+        let range = |query: IconReleaseQuery| match query {
+            IconReleaseQuery::Range(a, b) => (a, b),
+            other => panic!("Expected a range, got {other:?}"),
+        };
+        let (from, to) = range(IconReleaseQuery::between("2.0", "2.2").unwrap().until(2.1));
+        assert!(from < 2.0 && to > 2.1 && to < 2.2);
+        let (from, to) = range(IconReleaseQuery::version("2.2").unwrap().until(2.1));
+        assert!(from > to);
+        let (from, to) = range(IconReleaseQuery::version("2.0").unwrap().until(2.1));
+        assert!(from < 2.0 && to > 2.0 && to < 2.1);
+    }
+
+    #[test]
+    fn search_threshold_defaults_and_is_floored() {
+        let db = db();
+        assert_eq!(db.effective_threshold(None), 0.2);
+        assert_eq!(db.effective_threshold(Some(0.5)), 0.5);
+        assert_eq!(db.effective_threshold(Some(0.0)), 0.1);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::metrics;
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use sea_orm::DbErr;
+use serde::Serialize;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+tokio::task_local! {
+    /// The `X-Request-Id` of the request being handled, included in error bodies so that clients
+    /// can quote it when reporting failures.
+    pub static REQUEST_ID: String;
+}
+
+/// Errors returned by API handlers, rendered as an [`ErrorResponse`] JSON body.
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    NotFound(String),
+    /// A missing icon, along with the names of similar icons the client may have meant.
+    #[error("Icon not found: {name}")]
+    IconNotFound {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    #[error("{0}")]
+    TooManyRequests(String),
+    #[error("{0}")]
+    Unavailable(String),
+    #[error("Internal server error")]
+    Database(#[from] DbErr),
+    #[error("Internal server error")]
+    Internal(String),
+}
+
+impl ApiError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::NotFound(_) | ApiError::IconNotFound { .. } => "not_found",
+            ApiError::TooManyRequests(_) => "rate_limited",
+            ApiError::Unavailable(_) => "unavailable",
+            ApiError::Database(_) | ApiError::Internal(_) => "internal_error",
+        }
+    }
+}
+
+/// The body of every error response.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: ErrorDetail,
+    /// Names of existing icons similar to a requested one that was not found.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[schema(example = json!(["cube", "cube-transparent"]))]
+    pub suggestions: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorDetail {
+    /// A stable, machine-readable error code.
+    #[schema(example = "not_found")]
+    pub code: String,
+    /// A human-readable description of the error.
+    #[schema(example = "Icon not found: 2884")]
+    pub message: String,
+    /// The `X-Request-Id` of the failed request, for correlating with server logs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "9b2f6a53-2c41-4e8e-a3a1-6f0e4f1f2d6b")]
+    pub request_id: Option<String>,
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::NotFound(_) | ApiError::IconNotFound { .. } => StatusCode::NOT_FOUND,
+            ApiError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Database(_) | ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            ApiError::Database(e) => {
+                if matches!(e, DbErr::ConnectionAcquire(_)) {
+                    metrics::record_pool_acquire_failure();
+                }
+                tracing::error!("Database error: {e}")
+            }
+            ApiError::Internal(e) => tracing::error!("Internal error: {e}"),
+            _ => tracing::info!("{}: {}", self.code(), self),
+        }
+
+        HttpResponse::build(self.status_code()).json(ErrorResponse {
+            error: ErrorDetail {
+                code: self.code().to_string(),
+                message: self.to_string(),
+                request_id: REQUEST_ID.try_with(Clone::clone).ok(),
+            },
+            suggestions: match self {
+                ApiError::IconNotFound { suggestions, .. } => suggestions.clone(),
+                _ => vec![],
+            },
+        })
+    }
+}
@@ -0,0 +1,47 @@
+use crate::icons::Icon;
+
+/// Separator for list fields within a single CSV cell, matching the AppSheet table.
+const LIST_DELIMITER: &str = ", ";
+
+const CSV_HEADER: [&str; 9] = [
+    "id",
+    "name",
+    "alias",
+    "code",
+    "status",
+    "categories",
+    "tags",
+    "released_at",
+    "published",
+];
+
+/// Render icon metadata as CSV, one row per icon. List fields are joined into a single cell, and
+/// absent values are left empty.
+pub fn icons_to_csv(icons: &[Icon]) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(CSV_HEADER)?;
+    for icon in icons {
+        let categories = icon
+            .search_categories
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(LIST_DELIMITER);
+        writer.write_record([
+            icon.id.to_string(),
+            icon.name.clone(),
+            icon.alias.clone().unwrap_or_default(),
+            icon.code.map(|c| c.to_string()).unwrap_or_default(),
+            icon.status.to_string(),
+            categories,
+            icon.tags.join(LIST_DELIMITER),
+            icon.released_at
+                .map(|v| format!("{v:?}"))
+                .unwrap_or_default(),
+            icon.published.to_string(),
+        ])?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| csv::Error::from(e.into_error()))
+}
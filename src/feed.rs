@@ -0,0 +1,69 @@
+use crate::entities::icons;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::fmt::Write;
+
+/// Escape text for use in XML content and attribute values.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Render an Atom feed of released icons, newest first. `base_url` is the scheme and host the
+/// feed is served from, used to build absolute links.
+///
+/// Icons only record the library version they were released in, not a date, so every entry is
+/// stamped with `updated`; readers deduplicate entries by their stable IDs.
+pub fn atom(models: &[icons::Model], base_url: &str, updated: DateTime<Utc>) -> String {
+    let base_url = escape(base_url.trim_end_matches('/'));
+    let updated = updated.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    let mut xml = String::new();
+    let _ = write!(
+        xml,
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <id>{base_url}/v1/feed.xml</id>
+  <title>New Phosphor Icons</title>
+  <subtitle>Recently released icons, newest first.</subtitle>
+  <link rel="self" type="application/atom+xml" href="{base_url}/v1/feed.xml"/>
+  <link rel="alternate" href="https://phosphoricons.com"/>
+  <author><name>Phosphor Icons</name></author>
+  <updated>{updated}</updated>
+"#
+    );
+
+    for model in models {
+        let name = escape(&model.name);
+        let version = model
+            .released_at
+            .map(|v| format!("{v:.1}"))
+            .unwrap_or_default();
+        let tags = escape(&model.tags.join(", "));
+        let _ = write!(
+            xml,
+            r#"  <entry>
+    <id>{base_url}/v1/icon/{id}</id>
+    <title>{name} (v{version})</title>
+    <link rel="alternate" type="image/svg+xml" href="{base_url}/v1/icon/{id}/regular.svg"/>
+    <category term="v{version}" label="Version {version}"/>
+    <updated>{updated}</updated>
+    <summary>{name} was released in version {version}. Tags: {tags}</summary>
+  </entry>
+"#,
+            id = model.id,
+        );
+    }
+
+    xml.push_str("</feed>\n");
+    xml
+}
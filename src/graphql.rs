@@ -0,0 +1,307 @@
+use crate::{app::AppState, db, entities, icons, svgs::Svg};
+use async_graphql::{
+    dataloader::{DataLoader, Loader},
+    Context, EmptyMutation, EmptySubscription, Enum, InputObject, MaybeUndefined, Object, Result,
+    Schema, SimpleObject,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub type PhosphorSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Build the GraphQL schema, resolving against the same database as the REST API.
+pub fn schema(state: Arc<AppState>) -> PhosphorSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(DataLoader::new(SvgLoader(state.clone()), tokio::spawn))
+        .data(state)
+        .finish()
+}
+
+fn state<'a>(ctx: &Context<'a>) -> &'a AppState {
+    ctx.data_unchecked::<Arc<AppState>>()
+}
+
+/// Loads the SVGs of every icon in a response with a single query, rather than one per icon.
+pub struct SvgLoader(Arc<AppState>);
+
+impl Loader<i32> for SvgLoader {
+    type Value = HashMap<icons::IconWeight, Svg>;
+    type Error = Arc<sea_orm::DbErr>;
+
+    async fn load(&self, keys: &[i32]) -> Result<HashMap<i32, Self::Value>, Self::Error> {
+        self.0
+            .db
+            .get_svg_weights_by_icon_ids(keys)
+            .await
+            .map_err(Arc::new)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Enum)]
+pub enum Weight {
+    Thin,
+    Light,
+    Regular,
+    Bold,
+    Fill,
+    Duotone,
+}
+
+impl From<Weight> for icons::IconWeight {
+    fn from(weight: Weight) -> Self {
+        match weight {
+            Weight::Thin => icons::IconWeight::Thin,
+            Weight::Light => icons::IconWeight::Light,
+            Weight::Regular => icons::IconWeight::Regular,
+            Weight::Bold => icons::IconWeight::Bold,
+            Weight::Fill => icons::IconWeight::Fill,
+            Weight::Duotone => icons::IconWeight::Duotone,
+        }
+    }
+}
+
+impl From<&icons::IconWeight> for Weight {
+    fn from(weight: &icons::IconWeight) -> Self {
+        match weight {
+            icons::IconWeight::Thin => Weight::Thin,
+            icons::IconWeight::Light => Weight::Light,
+            icons::IconWeight::Regular => Weight::Regular,
+            icons::IconWeight::Bold => Weight::Bold,
+            icons::IconWeight::Fill => Weight::Fill,
+            icons::IconWeight::Duotone => Weight::Duotone,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct IconSvg {
+    pub weight: Weight,
+    pub src: String,
+}
+
+/// An icon. Enumerations such as `status` and `searchCategories` are exposed as the same strings
+/// used by the REST API.
+pub struct Icon(icons::Icon);
+
+#[Object]
+impl Icon {
+    async fn id(&self) -> i32 {
+        self.0.id
+    }
+
+    async fn rid(&self) -> &str {
+        &self.0.rid
+    }
+
+    /// The kebab-case name of the icon.
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn alias(&self) -> Option<&str> {
+        self.0.alias.as_deref()
+    }
+
+    /// The decimal unicode codepoint of the icon in font-based libraries.
+    async fn code(&self) -> Option<i32> {
+        self.0.code
+    }
+
+    async fn status(&self) -> String {
+        self.0.status.to_string()
+    }
+
+    async fn search_categories(&self) -> Vec<String> {
+        self.0
+            .search_categories
+            .iter()
+            .map(|c| c.to_string())
+            .collect()
+    }
+
+    async fn figma_category(&self) -> String {
+        self.0.figma_category.to_string()
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.0.tags
+    }
+
+    async fn notes(&self) -> Option<&str> {
+        self.0.notes.as_deref()
+    }
+
+    async fn released_at(&self) -> Option<f64> {
+        self.0.released_at
+    }
+
+    async fn last_updated_at(&self) -> Option<f64> {
+        self.0.last_updated_at
+    }
+
+    async fn deprecated_at(&self) -> Option<f64> {
+        self.0.deprecated_at
+    }
+
+    async fn published(&self) -> bool {
+        self.0.published
+    }
+
+    /// SVG source for the requested weights, or every available weight when none are given.
+    /// Weights that have not been synced are omitted.
+    async fn svgs(&self, ctx: &Context<'_>, weights: Option<Vec<Weight>>) -> Result<Vec<IconSvg>> {
+        let mut svgmap = ctx
+            .data_unchecked::<DataLoader<SvgLoader>>()
+            .load_one(self.0.id)
+            .await?
+            .unwrap_or_default();
+        let wanted =
+            weights.unwrap_or_else(|| icons::IconWeight::ALL.iter().map(Weight::from).collect());
+        Ok(wanted
+            .into_iter()
+            .filter_map(|weight| {
+                svgmap
+                    .remove(&icons::IconWeight::from(weight))
+                    .map(|svg| IconSvg {
+                        weight,
+                        src: svg.src,
+                    })
+            })
+            .collect())
+    }
+}
+
+impl From<entities::icons::Model> for Icon {
+    fn from(model: entities::icons::Model) -> Self {
+        Icon(icons::Icon::from(model))
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct ScoredIcon {
+    pub icon: Icon,
+    /// The relevance of the icon to the search term, from `0.0` to `1.0`.
+    pub score: f64,
+}
+
+/// Filters for `icons`, with the same semantics as the `/v1/icons` query parameters.
+#[derive(Default, InputObject)]
+pub struct IconFilter {
+    /// Kebab-case icon name, supporting `*` wildcards at either end.
+    pub name: Option<String>,
+    /// Release version or range, e.g. `2.1`, `..1.4`, or `1.5..2.0`.
+    pub released: Option<String>,
+    /// Release statuses, e.g. `Implemented`.
+    pub status: Option<Vec<String>>,
+    /// Search categories, e.g. `Design`.
+    pub category: Option<Vec<String>>,
+    pub tags: Option<Vec<String>>,
+    /// Only icons with a stored SVG in this weight.
+    pub has_weight: Option<Weight>,
+    /// Only icons with (or, when false, without) notes.
+    pub has_notes: Option<bool>,
+    /// Whether to return published icons (the default), unpublished icons, or either when null.
+    pub published: MaybeUndefined<bool>,
+    /// Defaults to 100, and is capped at 500 unless the server is configured otherwise.
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+fn parse_all<T: FromStr<Err = String>>(values: Option<Vec<String>>) -> Result<Option<Vec<T>>> {
+    values
+        .map(|values| values.iter().map(|v| T::from_str(v)).collect())
+        .transpose()
+        .map_err(Into::into)
+}
+
+impl TryFrom<IconFilter> for db::IconQuery {
+    type Error = async_graphql::Error;
+
+    fn try_from(filter: IconFilter) -> Result<Self> {
+        Ok(db::IconQuery {
+            name: filter.name,
+            released: filter
+                .released
+                .as_deref()
+                .map(db::IconReleaseQuery::from_str)
+                .transpose()?,
+            status: parse_all(filter.status)?,
+            category: parse_all(filter.category)?,
+            tags: filter.tags,
+            has_weight: filter.has_weight.map(Into::into),
+            has_notes: filter.has_notes,
+            published: Some(match filter.published {
+                MaybeUndefined::Undefined | MaybeUndefined::Value(true) => db::Ternary::True,
+                MaybeUndefined::Value(false) => db::Ternary::False,
+                MaybeUndefined::Null => db::Ternary::Any,
+            }),
+            limit: filter.limit,
+            offset: filter.offset,
+            ..Default::default()
+        })
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Fetch an icon by its ID.
+    async fn icon(&self, ctx: &Context<'_>, id: i32) -> Result<Option<Icon>> {
+        Ok(state(ctx).db.get_icon_by_id(id).await?.map(Icon::from))
+    }
+
+    /// List icons matching a filter, ordered by name.
+    async fn icons(&self, ctx: &Context<'_>, filter: Option<IconFilter>) -> Result<Vec<Icon>> {
+        let state = state(ctx);
+        let query =
+            db::IconQuery::try_from(filter.unwrap_or_default())?.paginated(&state.config.page_size);
+        Ok(state
+            .db
+            .get_icons(&query)
+            .await?
+            .into_iter()
+            .map(Icon::from)
+            .collect())
+    }
+
+    /// Fuzzy search for icons, most relevant first. Returns at most `limit` results, 100 by
+    /// default and no more than 500 unless the server is configured otherwise. `threshold` is the
+    /// minimum similarity, from 0 to 1, for a result to be returned.
+    async fn search(
+        &self,
+        ctx: &Context<'_>,
+        q: String,
+        limit: Option<u64>,
+        offset: Option<u64>,
+        threshold: Option<f64>,
+    ) -> Result<Vec<ScoredIcon>> {
+        let search = db::IconSearch {
+            q,
+            limit,
+            offset,
+            threshold,
+            ..Default::default()
+        };
+        search.validate()?;
+        let (results, _) = state(ctx).search(&search).await?;
+        Ok(results
+            .into_iter()
+            .map(|r| ScoredIcon {
+                icon: Icon::from(r.icon),
+                score: r.score,
+            })
+            .collect())
+    }
+
+    /// Every search category.
+    async fn categories(&self) -> Vec<String> {
+        icons::Category::ALL.iter().map(|c| c.to_string()).collect()
+    }
+
+    /// Every unique tag.
+    async fn tags(&self, ctx: &Context<'_>) -> Result<Vec<String>> {
+        Ok(state(ctx).db.get_all_tags().await?)
+    }
+}
@@ -5,7 +5,7 @@ use utoipa::ToSchema;
 
 use crate::{entities, table::TableIcon};
 
-#[derive(Debug, Default, Serialize, ToSchema)]
+#[derive(Debug, Default, Deserialize, Serialize, ToSchema)]
 pub struct Icon {
     /// The unique ID of the icon in the database.
     #[serde(default)]
@@ -32,18 +32,21 @@ pub struct Icon {
     #[schema(example = "Implemented")]
     pub status: IconStatus,
 
-    /// A list of categories the icon belongs to, used for filtering in the API.
+    /// A list of categories the icon belongs to, used for filtering in the API. These are the
+    /// values accepted by the `category` parameter of [/v1/icons](#tag/icon-endpoints/GET/v1/icons).
     #[schema(example = json!(["Design", "Games", "Objects"]))]
     pub search_categories: Vec<Category>,
 
-    /// A string representing the category the icon belongs to in the Figma library, not used for
-    /// filtering in the API.
-    pub category: FigmaCategory,
+    /// The section the icon belongs to in the Figma library. This is informational only and is
+    /// not accepted by the `category` filter; use `search_categories` for filtering.
+    #[schema(example = "Design")]
+    pub figma_category: FigmaCategory,
 
     /// A list of string tags associated with the icon.
     #[schema(example = json!(["square", "box", "3d", "volume", "blocks"]))]
     pub tags: Vec<String>,
 
+    /// Design or usage notes on the icon, if any.
     pub notes: Option<String>,
 
     /// A float in the format `<major>.<minor>` representing the version in which the icon was
@@ -78,7 +81,8 @@ impl From<entities::icons::Model> for Icon {
                 .into_iter()
                 .map(|s| Category::from_str(&s).unwrap_or(Category::Unknown))
                 .collect(),
-            category: FigmaCategory::from_str(&model.category).unwrap_or(FigmaCategory::Unknown),
+            figma_category: FigmaCategory::from_str(&model.category)
+                .unwrap_or(FigmaCategory::Unknown),
             tags: model.tags,
             notes: model.notes,
             released_at: model.released_at,
@@ -99,7 +103,7 @@ impl From<TableIcon> for Icon {
             code: icon.code,
             status: icon.status,
             search_categories: icon.search_categories,
-            category: icon.category,
+            figma_category: icon.category,
             tags: icon.tags,
             notes: icon.notes,
             released_at: icon.released_at,
@@ -132,6 +136,18 @@ impl IconWeight {
         IconWeight::Fill,
         IconWeight::Duotone,
     ];
+
+    /// The weights to try, in order, when this one is missing: this weight, then regular, then
+    /// every other weight.
+    pub fn fallback_chain(&self) -> Vec<IconWeight> {
+        let mut chain = vec![self.clone()];
+        for weight in std::iter::once(IconWeight::Regular).chain(IconWeight::ALL) {
+            if !chain.contains(&weight) {
+                chain.push(weight);
+            }
+        }
+        chain
+    }
 }
 
 impl Display for IconWeight {
@@ -185,6 +201,12 @@ impl IconStatus {
         IconStatus::Deprecated,
         IconStatus::None,
     ];
+    /// The statuses of icons still being designed, in pipeline order.
+    pub const UPCOMING: [IconStatus; 3] = [
+        IconStatus::Backlog,
+        IconStatus::Designing,
+        IconStatus::Designed,
+    ];
 }
 
 impl FromStr for IconStatus {
@@ -438,3 +460,173 @@ pub struct LibraryInfo {
     #[schema(example = 1512)]
     pub count: u64,
 }
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CategoryCount {
+    pub category: Category,
+    /// The number of published icons in the category.
+    #[schema(example = 412)]
+    pub count: u64,
+}
+
+/// A minimal icon reference, for search-as-you-type suggestions.
+#[derive(Debug, Serialize, ToSchema, FromQueryResult)]
+pub struct IconName {
+    #[schema(example = 2884)]
+    pub id: i32,
+    /// The kebab-case name of the icon.
+    #[schema(example = "cube")]
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Deprecation {
+    /// The kebab-case name of the deprecated icon.
+    #[schema(example = "instagram-logo")]
+    pub name: String,
+    /// A float in the format `<major>.<minor>` representing the version in which the icon was
+    /// deprecated.
+    #[schema(example = 2.0)]
+    pub deprecated_at: Option<f64>,
+    /// The kebab-case name of the icon that replaces it, or `null` if there is none.
+    #[schema(example = "instagram")]
+    pub replacement: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagCount {
+    #[schema(example = "arrows")]
+    pub tag: String,
+    /// The number of icons carrying the tag.
+    #[schema(example = 42)]
+    pub count: usize,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchedField {
+    Name,
+    Alias,
+    Tag,
+}
+
+/// Where a search term appears in an icon, so that clients can emphasize it.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Highlight {
+    pub matched_field: MatchedField,
+    /// The matching tag, when `matched_field` is `tag`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "box")]
+    pub tag: Option<String>,
+    /// The `[start, end)` character ranges of the field that match the search, in order.
+    #[schema(example = json!([[0, 4]]))]
+    pub ranges: Vec<[usize; 2]>,
+}
+
+impl Highlight {
+    /// Locate the words of any of `terms` in the icon's name, falling back to its alias and then
+    /// its tags. Returns `None` if no field contains them, as for icons matched by a typo.
+    pub fn find(icon: &entities::icons::Model, terms: &[String]) -> Option<Self> {
+        let words = terms
+            .iter()
+            .flat_map(|term| term.split(|c: char| c.is_whitespace() || c == '-'))
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+
+        let highlight = |matched_field, tag: Option<&String>, field: &str| {
+            let ranges = match_ranges(field, &words);
+            (!ranges.is_empty()).then(|| Highlight {
+                matched_field,
+                tag: tag.cloned(),
+                ranges,
+            })
+        };
+
+        highlight(MatchedField::Name, None, &icon.name)
+            .or_else(|| {
+                let alias = icon.alias.as_deref()?;
+                highlight(MatchedField::Alias, None, alias)
+            })
+            .or_else(|| {
+                icon.tags
+                    .iter()
+                    .find_map(|tag| highlight(MatchedField::Tag, Some(tag), tag))
+            })
+    }
+}
+
+/// The merged character ranges of `field` covered by any occurrence of `words`.
+fn match_ranges(field: &str, words: &[String]) -> Vec<[usize; 2]> {
+    let field = field.to_lowercase();
+    let char_offset = |byte: usize| field[..byte].chars().count();
+
+    let mut ranges = words
+        .iter()
+        .flat_map(|word| {
+            field
+                .match_indices(word.as_str())
+                .map(|(start, word)| [char_offset(start), char_offset(start + word.len())])
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    ranges.sort_unstable();
+
+    let mut merged: Vec<[usize; 2]> = Vec::with_capacity(ranges.len());
+    for [start, end] in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last[1] => last[1] = last[1].max(end),
+            _ => merged.push([start, end]),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weight_fallback_prefers_regular_then_library_order() {
+        assert_eq!(
+            IconWeight::Duotone.fallback_chain(),
+            vec![
+                IconWeight::Duotone,
+                IconWeight::Regular,
+                IconWeight::Thin,
+                IconWeight::Light,
+                IconWeight::Bold,
+                IconWeight::Fill,
+            ]
+        );
+        assert_eq!(IconWeight::Regular.fallback_chain(), {
+            let mut chain = IconWeight::ALL.to_vec();
+            chain.retain(|weight| *weight != IconWeight::Regular);
+            chain.insert(0, IconWeight::Regular);
+            chain
+        });
+    }
+
+    #[test]
+    fn icon_names_both_category_fields_unambiguously() {
+        let icon = Icon {
+            id: 2884,
+            name: "cube".to_string(),
+            search_categories: vec![Category::Design, Category::Objects],
+            figma_category: FigmaCategory::Design,
+            ..Icon::default()
+        };
+        let json = serde_json::to_value(&icon).unwrap();
+        assert_eq!(json["figma_category"], "Design");
+        assert_eq!(
+            json["search_categories"],
+            serde_json::json!(["Design", "Objects"])
+        );
+        assert!(json.get("category").is_none());
+
+        let parsed: Icon = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(parsed.figma_category, FigmaCategory::Design);
+        assert_eq!(parsed.search_categories, icon.search_categories);
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{error::ApiError, icons};
+use actix_web::{
+    body::BoxBody,
+    http::header::{self, HeaderValue},
+    HttpRequest, HttpResponse, Responder, ResponseError,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// The media type of [JSON:API](https://jsonapi.org) documents. Clients opt in to JSON:API
+/// responses by sending it in `Accept`.
+pub const MEDIA_TYPE: &str = "application/vnd.api+json";
+
+/// Whether the client asked for a JSON:API document.
+pub fn is_requested(req: &HttpRequest) -> bool {
+    req.headers()
+        .get_all(header::ACCEPT)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media| {
+            media
+                .split(';')
+                .next()
+                .is_some_and(|m| m.trim().eq_ignore_ascii_case(MEDIA_TYPE))
+        })
+}
+
+/// A JSON:API resource object.
+#[derive(Debug, Serialize)]
+pub struct Resource {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub id: String,
+    pub attributes: Map<String, Value>,
+}
+
+/// Types that can be represented as a JSON:API resource. By default, every serialized field
+/// except `id` becomes an attribute.
+pub trait ToResource: Serialize {
+    const TYPE: &'static str;
+
+    fn resource_id(&self) -> String;
+
+    fn to_resource(&self) -> Resource {
+        let mut attributes = match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        };
+        attributes.remove("id");
+        Resource {
+            kind: Self::TYPE,
+            id: self.resource_id(),
+            attributes,
+        }
+    }
+}
+
+impl ToResource for icons::Icon {
+    const TYPE: &'static str = "icon";
+
+    fn resource_id(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+/// A top-level JSON:API document, with `data` holding one resource or a list of them.
+#[derive(Debug, Serialize)]
+pub struct Document<D> {
+    pub data: D,
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub meta: Map<String, Value>,
+}
+
+impl<D> Document<D> {
+    pub fn new(data: D) -> Self {
+        Self {
+            data,
+            meta: Map::new(),
+        }
+    }
+
+    pub fn meta(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.meta.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+/// Responds with a JSON:API document, using the JSON:API media type.
+pub struct JsonApi<D>(pub Document<D>);
+
+impl<D: Serialize> Responder for JsonApi<D> {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse {
+        match serde_json::to_string(&self.0) {
+            Ok(body) => HttpResponse::Ok()
+                .insert_header((header::CONTENT_TYPE, HeaderValue::from_static(MEDIA_TYPE)))
+                .body(body),
+            Err(e) => ApiError::Internal(format!("Failed to serialize JSON:API document: {e}"))
+                .error_response(),
+        }
+    }
+}
@@ -1,6 +1,18 @@
 pub mod app;
+pub mod archive;
+pub mod auth;
+pub mod config;
 pub mod db;
 pub mod entities;
+pub mod error;
+pub mod export;
+pub mod feed;
+pub mod graphql;
 pub mod icons;
+pub mod jsonapi;
+pub mod metrics;
+pub mod rate_limit;
+pub mod renders;
 pub mod svgs;
+pub mod synonyms;
 pub mod table;
@@ -1,11 +1,18 @@
 use actix_web::{
+    body::MessageBody,
+    dev::{ServiceFactory, ServiceRequest, ServiceResponse},
     get, http,
     middleware::{self, Logger},
-    web, App, HttpResponse, HttpServer, Responder,
+    patch, post, web, App, HttpRequest, HttpResponse, HttpServer, Responder,
+};
+use phosphor_server::{
+    app,
+    config::Config,
+    error::{ApiError, ErrorResponse},
+    graphql::PhosphorSchema,
 };
-use phosphor_server::app;
 use serde::Serialize;
-use std::{net::Ipv4Addr, time::Duration};
+use std::time::Duration;
 use tracing_subscriber::{filter::EnvFilter, prelude::*};
 use utoipa::{self, OpenApi};
 use utoipa_actix_web::{scope, AppExt};
@@ -26,117 +33,280 @@ use utoipa_scalar::{Scalar, Servable as ScalarServable};
             description = "Search and filter existing, deprecated, and upcoming icons, and retrieve SVG source code for specific icons."
         ),
         (name = "Metadata endpoints", description = "Query for metadata about the API, including available categories and tags."),
+        (name = "Admin endpoints", description = "Maintenance endpoints for editing the icon catalog. Requires an `Authorization: Bearer <key>` or `X-API-Key: <key>` header matching one of the comma-separated keys in the server's `PHOSPHOR_ADMIN_KEY`."),
         (name = "Other endpoints", description = "Other endpoints"),
     ),
 )]
 struct Api;
 
+/// The API and its middleware, as served by each worker. Metrics are only served alongside the API
+/// when `serve_metrics` is set.
+fn build_app(
+    data: web::Data<app::AppState>,
+    schema: web::Data<PhosphorSchema>,
+    serve_metrics: bool,
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let cors_origins = data.config.cors_allowed_origins.clone();
+    App::new()
+        .into_utoipa_app()
+        .app_data(data)
+        .map(|app| {
+            app.wrap(middleware::from_fn(rate_limit::limit))
+                .wrap(middleware::DefaultHeaders::new().add(("Timing-Allow-Origin", "*")))
+                .wrap(middleware::from_fn(caching::cache_control))
+                .wrap(middleware::from_fn(timing::response_time))
+                .wrap(middleware::from_fn(metrics::track_requests))
+                .wrap(Logger::default())
+                .wrap(middleware::from_fn(request_id::propagate))
+                // Outermost, so that preflights are answered before rate limiting and the
+                // headers added by every other middleware are exposed to browsers.
+                .wrap(cors::policy(&cors_origins))
+        })
+        .service(
+            scope::scope("/v1")
+                .service(icons::icon)
+                .service(icons::icon_by_name)
+                .service(icons::icon_by_alias)
+                .service(icons::icon_weights)
+                .service(icons::icon_svg)
+                .service(icons::icon_png)
+                .service(icons::icon_react)
+                .service(icons::all_icons)
+                .service(icons::latest_icons)
+                .service(icons::atom_feed)
+                .service(icons::random_icons)
+                .service(icons::sprite)
+                .service(icons::category_sprite)
+                .service(icons::stylesheet)
+                .service(icons::download)
+                .service(icons::batch_icons)
+                .service(icons::search_icons)
+                .service(icons::autocomplete)
+                .service(icons::deprecations)
+                .service(icons::deprecated_icons)
+                .service(icons::codepoints)
+                .service(icons::version_changes)
+                .service(icons::version_diff)
+                .service(icons::pipeline)
+                .service(icons::upcoming)
+                .service(metadata::info)
+                .service(metadata::categories)
+                .service(metadata::tags)
+                .service(metadata::filters)
+                .service(scope::scope("/admin").service(admin::sync)),
+        )
+        .service(
+            scope::scope("/admin")
+                .app_data(web::JsonConfig::default().limit(admin::MAX_BODY_SIZE))
+                .service(admin::patch_icon)
+                .service(admin::import)
+                .service(admin::prime_cache)
+                .service(admin::prime_status),
+        )
+        .service(health::health_check)
+        .openapi_service(|api| {
+            let api = Api::openapi().merge_from(api);
+            Scalar::with_url("/docs", api).custom_html(include_str!("../public/index.html"))
+        })
+        .into_app()
+        .service(health::dump)
+        .app_data(schema)
+        .service(graphql::execute)
+        .service(graphql::playground)
+        .configure(|cfg| {
+            if serve_metrics {
+                cfg.service(metrics::export);
+            }
+        })
+        .service(actix_files::Files::new("/", "./public"))
+}
+
 #[actix_web::main]
 async fn main() -> Result<(), std::io::Error> {
     dotenvy::dotenv().ok();
 
+    let tracer_provider = telemetry::otlp_tracer_provider();
     tracing_subscriber::registry()
         .with(tracing_subscriber::fmt::layer().pretty())
+        .with(tracer_provider.as_ref().map(|provider| {
+            use opentelemetry::trace::TracerProvider as _;
+            tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+        }))
         .with(EnvFilter::from_default_env())
         .init();
 
-    let app = app::AppState::init().await?;
+    let config = Config::from_env().map_err(|e| {
+        tracing::error!("Invalid configuration: {e}");
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string())
+    })?;
+
+    // `phosphor-server migrate` creates or upgrades the database schema, then exits.
+    if std::env::args().nth(1).as_deref() == Some("migrate") {
+        return migrate::run(&config).await;
+    }
+
+    let (host, port, workers) = (config.host.clone(), config.port, config.workers);
+    let metrics_addr = config.metrics_addr.clone();
+    let shutdown_timeout = config.shutdown_timeout;
+
+    let app = app::AppState::init(config).await?;
     let data = web::Data::new(app);
-    let url = std::env::var("HOST").unwrap_or(Ipv4Addr::UNSPECIFIED.to_string());
-    let port = std::env::var("PORT")
-        .unwrap_or_else(|_| "8080".to_string())
-        .parse::<u16>()
-        .expect("PORT must be a valid u16");
-
-    HttpServer::new(move || {
-        App::new()
-            .into_utoipa_app()
-            .app_data(data.clone())
-            .map(|app| {
-                app.wrap(
-                    middleware::DefaultHeaders::new()
-                        .add((http::header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"))
-                        .add((http::header::ACCESS_CONTROL_MAX_AGE, 3600)),
-                )
-                .wrap(Logger::default())
-            })
-            .service(
-                scope::scope("/v1")
-                    .service(icons::icon)
-                    .service(icons::all_icons)
-                    .service(icons::search_icons)
-                    .service(metadata::info)
-                    .service(metadata::categories)
-                    .service(metadata::tags),
+    let state = data.clone();
+    let schema = web::Data::new(phosphor_server::graphql::schema(data.clone().into_inner()));
+
+    // Metrics can be served on a separate address, e.g. one reachable only from a private network,
+    // in which case they are not exposed alongside the API.
+    let metrics_server = match &metrics_addr {
+        Some(addr) => {
+            tracing::info!("Serving metrics on {addr}");
+            Some(
+                HttpServer::new(|| App::new().service(metrics::export))
+                    .workers(1)
+                    .disable_signals()
+                    .bind(addr)?
+                    .run(),
             )
-            .service(health::health_check)
-            .openapi_service(|api| {
-                let api = Api::openapi().merge_from(api);
-                Scalar::with_url("/docs", api).custom_html(include_str!("../public/index.html"))
-            })
-            .into_app()
-            .service(health::dump)
-            .service(actix_files::Files::new("/", "./public"))
-    })
-    // NOTE: the app requires a minimum of 3 workers to run the docs server, dispatch, and at
-    // least one request handler. We should look at real-world utilization once this is public.
-    .workers(8)
-    .keep_alive(Duration::from_secs(120))
-    .bind((url, port))?
-    .run()
-    .await
+        }
+        None => None,
+    };
+    let serve_metrics = metrics_addr.is_none();
+
+    let server = HttpServer::new(move || build_app(data.clone(), schema.clone(), serve_metrics))
+        // NOTE: we should look at real-world utilization of workers once this is public.
+        .workers(workers)
+        .keep_alive(Duration::from_secs(120))
+        .shutdown_timeout(shutdown_timeout)
+        .disable_signals()
+        .bind((host, port))?
+        .run();
+
+    // The pool is sampled in the background, since metrics may be served without access to it.
+    let pool_state = state.clone();
+    actix_web::rt::spawn(async move {
+        let mut interval = tokio::time::interval(metrics::POOL_SAMPLE_INTERVAL);
+        loop {
+            interval.tick().await;
+            pool_state.db.record_pool_usage();
+        }
+    });
+
+    let mut handles = vec![server.handle()];
+    handles.extend(metrics_server.as_ref().map(|s| s.handle()));
+    actix_web::rt::spawn(shutdown::on_signal(handles, shutdown_timeout));
+
+    let res = match metrics_server {
+        Some(metrics_server) => tokio::try_join!(server, metrics_server).map(|_| ()),
+        None => server.await,
+    };
+    tracing::info!("Server stopped, closing database connections");
+    if let Err(e) = state.db.close().await {
+        tracing::warn!("Failed to close database connections: {e}");
+    }
+    tracing::info!("Shutdown complete");
+
+    if let Some(provider) = tracer_provider {
+        if let Err(e) = provider.shutdown() {
+            tracing::warn!("Failed to flush OTLP spans: {e}");
+        }
+    }
+    res
+}
+
+mod telemetry {
+    use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+
+    /// Build a tracer provider exporting spans over OTLP/HTTP when `OTEL_EXPORTER_OTLP_ENDPOINT`
+    /// is set, e.g. to `http://localhost:4318` for a local Jaeger or Tempo. The standard
+    /// `OTEL_*` variables, such as `OTEL_SERVICE_NAME`, are honored.
+    pub fn otlp_tracer_provider() -> Option<SdkTracerProvider> {
+        let endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()?;
+        let exporter = match opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .build()
+        {
+            Ok(exporter) => exporter,
+            Err(e) => {
+                // The subscriber is not installed yet, so this cannot be logged through tracing.
+                eprintln!("Failed to create OTLP exporter for {endpoint}: {e}");
+                return None;
+            }
+        };
+
+        let mut resource = Resource::builder();
+        if std::env::var_os("OTEL_SERVICE_NAME").is_none() {
+            resource = resource.with_service_name(env!("CARGO_PKG_NAME"));
+        }
+        Some(
+            SdkTracerProvider::builder()
+                .with_batch_exporter(exporter)
+                .with_resource(resource.build())
+                .build(),
+        )
+    }
 }
 
 mod icons {
     use super::*;
-    use phosphor_server::{app, db, entities, icons};
+    use actix_web::http::header::TryIntoHeaderPair;
+    use phosphor_server::jsonapi::{self, ToResource};
+    use phosphor_server::{app, archive, db, entities, export, feed, icons, svgs};
     use serde_qs::actix::QsQuery;
     use std::collections::HashMap;
+    use std::str::FromStr;
     use utoipa::ToSchema;
 
+    /// SVG source for each weight, or `null` for weights that have not been synced.
     #[derive(Serialize, ToSchema)]
     pub struct IconWeightMap {
         #[schema(example = "<svg>...</svg>")]
-        regular: String,
+        regular: Option<String>,
         #[schema(example = "<svg>...</svg>")]
-        thin: String,
+        thin: Option<String>,
         #[schema(example = "<svg>...</svg>")]
-        light: String,
+        light: Option<String>,
         #[schema(example = "<svg>...</svg>")]
-        bold: String,
+        bold: Option<String>,
         #[schema(example = "<svg>...</svg>")]
-        fill: String,
+        fill: Option<String>,
         #[schema(example = "<svg>...</svg>")]
-        duotone: String,
+        duotone: Option<String>,
+    }
+
+    impl From<HashMap<icons::IconWeight, svgs::Svg>> for IconWeightMap {
+        fn from(mut map: HashMap<icons::IconWeight, svgs::Svg>) -> Self {
+            let mut take = |weight: icons::IconWeight| map.remove(&weight).map(|s| s.src);
+            Self {
+                regular: take(icons::IconWeight::Regular),
+                thin: take(icons::IconWeight::Thin),
+                light: take(icons::IconWeight::Light),
+                bold: take(icons::IconWeight::Bold),
+                fill: take(icons::IconWeight::Fill),
+                duotone: take(icons::IconWeight::Duotone),
+            }
+        }
     }
 
-    impl From<HashMap<String, entities::svgs::Model>> for IconWeightMap {
-        fn from(map: HashMap<String, entities::svgs::Model>) -> Self {
+    impl IconWeightMap {
+        fn encode(self, encoding: db::SvgEncoding) -> Self {
+            let encode = |src: Option<String>| match encoding {
+                db::SvgEncoding::Raw => src,
+                db::SvgEncoding::DataUri => src.as_deref().map(svgs::to_data_uri),
+            };
             Self {
-                regular: map
-                    .get(&icons::IconWeight::Regular.to_string())
-                    .map(|s| s.src.clone())
-                    .unwrap_or_default(),
-                thin: map
-                    .get(&icons::IconWeight::Thin.to_string())
-                    .map(|s| s.src.clone())
-                    .unwrap_or_default(),
-                light: map
-                    .get(&icons::IconWeight::Light.to_string())
-                    .map(|s| s.src.clone())
-                    .unwrap_or_default(),
-                bold: map
-                    .get(&icons::IconWeight::Bold.to_string())
-                    .map(|s| s.src.clone())
-                    .unwrap_or_default(),
-                fill: map
-                    .get(&icons::IconWeight::Fill.to_string())
-                    .map(|s| s.src.clone())
-                    .unwrap_or_default(),
-                duotone: map
-                    .get(&icons::IconWeight::Duotone.to_string())
-                    .map(|s| s.src.clone())
-                    .unwrap_or_default(),
+                regular: encode(self.regular),
+                thin: encode(self.thin),
+                light: encode(self.light),
+                bold: encode(self.bold),
+                fill: encode(self.fill),
+                duotone: encode(self.duotone),
             }
         }
     }
@@ -145,259 +315,2562 @@ mod icons {
     pub struct SingleIconResponse {
         /// Icon metadata
         icon: icons::Icon,
-        /// SVG code for the icon
-        svgs: IconWeightMap,
+        /// SVG code for the icon, or `null` if its assets have not been synced yet
+        svgs: Option<IconWeightMap>,
+    }
+
+    impl SingleIconResponse {
+        fn new(
+            model: entities::icons::Model,
+            svgmap: HashMap<icons::IconWeight, svgs::Svg>,
+            encoding: db::SvgEncoding,
+        ) -> Self {
+            let svgs = if svgmap.is_empty() {
+                tracing::warn!("No SVGs synced for icon: {}", model.id);
+                None
+            } else {
+                Some(IconWeightMap::from(svgmap).encode(encoding))
+            };
+            Self {
+                icon: icons::Icon::from(model),
+                svgs,
+            }
+        }
     }
 
     #[utoipa::path(
-        description = "Fetch an icon by its ID, returning the icon's metadata and SVG code.",
+        description = "Fetch an icon by its ID, returning the icon's metadata and SVG code. With `encode=datauri`, each weight's SVG is returned as a base64 `data:` URI instead of markup. Responses carry an `ETag` and honor `If-None-Match`.",
         params(
             ("id", example = 2884),
+            db::EncodingQuery,
         ),
         responses(
             (status = OK, body = SingleIconResponse, description = "Icon found"),
-            (status = NOT_FOUND, description = "Icon not found"),
-            (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+            (status = NOT_MODIFIED, description = "Icon unchanged since the given ETag"),
+            (status = NOT_FOUND, body = ErrorResponse, description = "Icon not found"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
         ),
         tag = "Icon endpoints",
     )]
     #[get("/icon/{id}")]
-    #[tracing::instrument(level = "info")]
-    async fn icon(data: web::Data<app::AppState>, id: web::Path<i32>) -> impl Responder {
+    #[tracing::instrument(level = "info", skip(req, data), fields(id = %id))]
+    async fn icon(
+        req: HttpRequest,
+        data: web::Data<app::AppState>,
+        id: web::Path<i32>,
+        encoding: web::Query<db::EncodingQuery>,
+    ) -> Result<HttpResponse, ApiError> {
         let id = id.into_inner();
-        match data.db.get_icon_by_id(id).await {
-            Ok(Some(icon)) => {
-                let icon = icons::Icon::from(icon);
-                if let Ok(svgmap) = data.db.get_icon_weights_by_icon_id(id).await {
-                    let svgs = IconWeightMap::from(svgmap);
-                    HttpResponse::Ok().json(SingleIconResponse { icon, svgs })
-                } else {
-                    tracing::error!("Failed to fetch SVGs for icon: {}", id);
-                    HttpResponse::InternalServerError().finish()
-                }
-            }
-            Ok(None) => {
-                tracing::info!("Icon not found: {}", id);
-                HttpResponse::NotFound().finish()
-            }
-            Err(_) => {
-                tracing::error!("Failed to fetch icons");
-                HttpResponse::InternalServerError().finish()
-            }
+        let encoding = encoding.into_inner().encode.unwrap_or_default();
+        match data.db.get_icon_by_id(id).await? {
+            Some(model) => single_icon_response(&req, &data, model, encoding).await,
+            None => Err(ApiError::NotFound(format!("Icon not found: {id}"))),
         }
     }
 
     #[derive(ToSchema, Serialize)]
-    pub struct MultipleIconResponse {
-        icons: Vec<icons::Icon>,
-        count: usize,
+    pub struct IconWeightsResponse {
+        /// Weights with SVG source available, in the order `thin`, `light`, `regular`, `bold`,
+        /// `fill`, `duotone`.
+        #[schema(example = json!(["regular", "bold"]))]
+        weights: Vec<icons::IconWeight>,
     }
 
-    impl MultipleIconResponse {
-        pub fn new(icons: Vec<icons::Icon>) -> Self {
-            let count = icons.len();
-            Self { icons, count }
+    #[utoipa::path(
+        description = "List the weights available for an icon, without fetching any SVG source.",
+        params(
+            ("id", example = 2884),
+        ),
+        responses(
+            (status = OK, body = IconWeightsResponse, description = "Icon found"),
+            (status = NOT_FOUND, body = ErrorResponse, description = "Icon not found"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/icon/{id}/weights")]
+    #[tracing::instrument(level = "info", skip(data), fields(id = %id))]
+    async fn icon_weights(
+        data: web::Data<app::AppState>,
+        id: web::Path<i32>,
+    ) -> Result<HttpResponse, ApiError> {
+        let id = id.into_inner();
+        let (model, weights) =
+            tokio::try_join!(data.db.get_icon_by_id(id), data.db.get_icon_weights(id))?;
+        if model.is_none() {
+            return Err(ApiError::NotFound(format!("Icon not found: {id}")));
         }
+        Ok(HttpResponse::Ok().json(IconWeightsResponse { weights }))
     }
 
+    /// How many similar names to suggest when a name lookup misses.
+    const NAME_SUGGESTIONS: u64 = 3;
+
     #[utoipa::path(
-        description = "Fetch icons from our database, with optional query parameters to filter by name, status, release version, tags, and categories.",
-        params(db::IconQuery),
+        description = "Fetch an icon by its kebab-case name, returning the icon's metadata and SVG code. Names are matched case-insensitively. If no icon has the name but one lists it as an alias, redirects to the current name. Otherwise, the 404 response suggests up to 3 similarly named icons. Responses carry an `ETag` and honor `If-None-Match`.",
+        params(
+            ("name", example = "cube"),
+        ),
         responses(
-            (status = OK, body = MultipleIconResponse),
-            (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+            (status = OK, body = SingleIconResponse, description = "Icon found"),
+            (status = MOVED_PERMANENTLY, description = "The name is an alias; `Location` points to the current name"),
+            (status = NOT_MODIFIED, description = "Icon unchanged since the given ETag"),
+            (status = NOT_FOUND, body = ErrorResponse, description = "Icon not found, with `suggestions` of similar names"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
         ),
         tag = "Icon endpoints",
     )]
-    #[get("/icons")]
-    #[tracing::instrument(level = "info")]
-    async fn all_icons(
+    #[get("/icon/by-name/{name}")]
+    #[tracing::instrument(level = "info", skip(req, data))]
+    async fn icon_by_name(
+        req: HttpRequest,
         data: web::Data<app::AppState>,
-        query: QsQuery<db::IconQuery>,
-    ) -> impl Responder {
-        let query = query.into_inner();
-        match data.db.get_icons(&query).await {
-            Ok(icons) => {
-                let icons = icons.into_iter().map(icons::Icon::from).collect::<Vec<_>>();
-                HttpResponse::Ok().json(MultipleIconResponse::new(icons))
-            }
-            Err(e) => {
-                tracing::error!("Failed to fetch icons for query: {:?}", e);
-                HttpResponse::InternalServerError().finish()
+        name: web::Path<String>,
+    ) -> Result<HttpResponse, ApiError> {
+        let name = name.into_inner().to_lowercase();
+        if let Some(model) = data.db.get_icon_by_name(&name).await? {
+            return single_icon_response(&req, &data, model, db::SvgEncoding::Raw).await;
+        }
+        match data.db.get_icon_by_alias(&name).await? {
+            Some(model) => Ok(HttpResponse::MovedPermanently()
+                .insert_header((
+                    http::header::LOCATION,
+                    format!("/v1/icon/by-name/{}", model.name),
+                ))
+                .finish()),
+            None => {
+                // Suggestions are a courtesy, so a failure to find any still reports the 404.
+                let suggestions = data
+                    .db
+                    .suggest_names(&name, NAME_SUGGESTIONS)
+                    .await
+                    .inspect_err(|e| tracing::warn!("Failed to suggest icon names: {e}"))
+                    .unwrap_or_default();
+                Err(ApiError::IconNotFound { name, suggestions })
             }
         }
     }
 
     #[utoipa::path(
-        description = "Fuzzy search for icons by semantic name, use-case, or other properties. Returns results along with a relevance score.",
-        params(db::IconSearch),
+        description = "Fetch the icon that replaced one previously known by the given name, returning the icon's metadata and SVG code. Use the `name` field of the response for the current name. Responses carry an `ETag` and honor `If-None-Match`.",
+        params(
+            ("alias", example = "instagram-logo"),
+        ),
         responses(
-            (status = OK, body = MultipleIconResponse),
-            (status = NOT_FOUND, description = "Icon not found"),
-            (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+            (status = OK, body = SingleIconResponse, description = "Icon found"),
+            (status = NOT_MODIFIED, description = "Icon unchanged since the given ETag"),
+            (status = NOT_FOUND, body = ErrorResponse, description = "No icon has this alias"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
         ),
         tag = "Icon endpoints",
     )]
-    #[get("/search")]
-    #[tracing::instrument(level = "info")]
-    async fn search_icons(
+    #[get("/icon/by-alias/{alias}")]
+    #[tracing::instrument(level = "info", skip(req, data))]
+    async fn icon_by_alias(
+        req: HttpRequest,
         data: web::Data<app::AppState>,
-        search: web::Query<db::IconSearch>,
-    ) -> impl Responder {
-        let search = search.into_inner();
-        match data.db.query_icons(&search).await {
-            Ok(icons) => {
-                let icons = icons.into_iter().map(icons::Icon::from).collect::<Vec<_>>();
-                HttpResponse::Ok().json(MultipleIconResponse::new(icons))
-            }
-            Err(_) => {
-                tracing::error!("Failed to fetch icon: {:?}", search);
-                HttpResponse::InternalServerError().finish()
-            }
+        alias: web::Path<String>,
+    ) -> Result<HttpResponse, ApiError> {
+        let alias = alias.into_inner().to_lowercase();
+        match data.db.get_icon_by_alias(&alias).await? {
+            Some(model) => single_icon_response(&req, &data, model, db::SvgEncoding::Raw).await,
+            None => Err(ApiError::NotFound(format!("No icon with alias: {alias}"))),
         }
     }
-}
-
-mod metadata {
-    use super::*;
-    use phosphor_server::icons;
-    use utoipa::ToSchema;
 
     #[utoipa::path(
-        description = "Describe the current state of the library, including the most recent version and the number of icons.",
+        description = "Fetch the raw SVG source for a single weight of an icon, served as `image/svg+xml` for direct use in `<img>` tags or CSS. Pass `color` to bake a fixed color into the source in place of `currentColor`.",
+        params(
+            ("id", example = 2884),
+            ("weight", example = "regular"),
+            db::SvgQuery,
+        ),
         responses(
-            (status = OK, description = "LibraryInfo", body = icons::LibraryInfo),
-            (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+            (status = OK, content_type = "image/svg+xml", body = String, description = "Icon SVG, or a placeholder marked with `X-Icon-Fallback: true` if the icon or weight is missing and `fallback=true`"),
+            (status = NOT_MODIFIED, description = "SVG unchanged since the given ETag"),
+            (status = BAD_REQUEST, body = ErrorResponse, description = "Invalid weight or color"),
+            (status = NOT_FOUND, body = ErrorResponse, description = "Icon or weight not found, without `fallback=true`"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
         ),
-        tag = "Metadata endpoints",
+        tag = "Icon endpoints",
     )]
-    #[get("/info")]
-    #[tracing::instrument(level = "info")]
-    async fn info(data: web::Data<app::AppState>) -> impl Responder {
-        match data.db.get_library_info().await {
-            Ok(info) => HttpResponse::Ok().json(info),
-            Err(e) => {
-                tracing::error!("Failed to fetch library info: {e}");
-                HttpResponse::InternalServerError().finish()
+    #[get("/icon/{id}/{weight}.svg")]
+    #[tracing::instrument(level = "info", skip(req, data))]
+    async fn icon_svg(
+        req: HttpRequest,
+        data: web::Data<app::AppState>,
+        path: web::Path<(i32, String)>,
+        query: web::Query<db::SvgQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        let (id, weight) = path.into_inner();
+        let requested = icons::IconWeight::from_str(&weight).map_err(ApiError::BadRequest)?;
+        let weight = served_weight(&data, id, &requested, query.strict).await?;
+        let src = match data.svg_source(id, &weight).await? {
+            Some(src) => src,
+            None if query.fallback.unwrap_or(false) => {
+                return fallback_svg(id, &weight, query.color.as_deref())
             }
+            None => {
+                return Err(ApiError::NotFound(format!(
+                    "SVG not found: {id} - {weight}"
+                )))
+            }
+        };
+        let src = match &query.color {
+            Some(color) => svgs::recolor(&src, color).map_err(ApiError::BadRequest)?,
+            None => src.to_string(),
+        };
+
+        let etag = conditional::etag(&src);
+        if let Some(res) = conditional::not_modified(&req, &etag) {
+            return Ok(res);
+        }
+
+        let mut res = HttpResponse::Ok();
+        res.content_type("image/svg+xml")
+            .insert_header(http::header::ETag(etag))
+            .insert_header(weight_cache_control(&requested, &weight));
+        if query.strict == Some(false) {
+            res.insert_header((WEIGHT_SERVED, weight.to_string()));
         }
+        Ok(res.body(src))
     }
 
-    #[derive(Serialize, ToSchema)]
-    struct CategoriesResponse {
-        categories: Vec<icons::Category>,
-        count: usize,
+    /// Reports the weight actually served when `strict=false`, which may differ from the one
+    /// requested.
+    const WEIGHT_SERVED: &str = "X-Icon-Weight-Served";
+
+    /// The weight to serve for a request: the one requested, unless it's missing and `strict` is
+    /// `false`, in which case the first one the icon has along its fallback chain.
+    async fn served_weight(
+        data: &app::AppState,
+        id: i32,
+        requested: &icons::IconWeight,
+        strict: Option<bool>,
+    ) -> Result<icons::IconWeight, ApiError> {
+        if strict.unwrap_or(true) {
+            return Ok(requested.clone());
+        }
+        Ok(data
+            .fallback_weight(id, requested)
+            .await?
+            .unwrap_or_else(|| requested.clone()))
+    }
+
+    /// Long-lived caching for the weight that was requested. A substituted weight isn't cached,
+    /// like [`fallback_svg`], so the requested one is picked up as soon as it's added.
+    fn weight_cache_control(
+        requested: &icons::IconWeight,
+        served: &icons::IconWeight,
+    ) -> http::header::CacheControl {
+        if served == requested {
+            http::header::CacheControl(vec![
+                http::header::CacheDirective::Public,
+                http::header::CacheDirective::MaxAge(SVG_MAX_AGE),
+            ])
+        } else {
+            http::header::CacheControl(vec![http::header::CacheDirective::NoStore])
+        }
+    }
+
+    /// The placeholder served for a missing icon when `fallback=true`. It isn't cached, so the
+    /// real icon is picked up as soon as it's added.
+    fn fallback_svg(
+        id: i32,
+        weight: &icons::IconWeight,
+        color: Option<&str>,
+    ) -> Result<HttpResponse, ApiError> {
+        let mut src = svgs::placeholder(id, weight);
+        if let Some(color) = color {
+            src = svgs::recolor(&src, color).map_err(ApiError::BadRequest)?;
+        }
+        Ok(HttpResponse::Ok()
+            .content_type("image/svg+xml")
+            .insert_header(("X-Icon-Fallback", "true"))
+            .insert_header(http::header::CacheControl(vec![
+                http::header::CacheDirective::NoStore,
+            ]))
+            .body(src))
     }
 
     #[utoipa::path(
-        description = "Fetch all icon categories from our database. These can be used as the `category` parameter in the [/v1/icons](#tag/icon-endpoints/GET/v1/icons) endpoint.",
-        responses((status = OK, body = CategoriesResponse)),
-        tag = "Metadata endpoints",
+        description = "Generate a React component for a single weight of an icon, named in PascalCase after the icon (`export const CubeFocus = (props) => ...`). SVG attributes are converted to their JSX spelling, and props are spread onto the `<svg>`. The icon is drawn in `currentColor`, so it inherits the surrounding text color.",
+        params(
+            ("id", example = 2884),
+            db::WeightQuery,
+        ),
+        responses(
+            (status = OK, content_type = "text/plain", body = String, description = "JSX component source"),
+            (status = NOT_FOUND, body = ErrorResponse, description = "Icon or weight not found"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/icon/{id}/react")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn icon_react(
+        data: web::Data<app::AppState>,
+        id: web::Path<i32>,
+        weight: web::Query<db::WeightQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        let id = id.into_inner();
+        let weight = weight.into_inner().weight.unwrap_or_default();
+        let (model, mut svgmap) = tokio::try_join!(
+            data.db.get_icon_by_id(id),
+            data.db.get_svg_weights_by_icon_id(id)
+        )?;
+        let model = model.ok_or_else(|| ApiError::NotFound(format!("Icon not found: {id}")))?;
+        let svg = svgmap
+            .remove(&weight)
+            .ok_or_else(|| ApiError::NotFound(format!("SVG not found: {id} - {weight}")))?;
+        let component = svgs::to_react_component(&svg.src, &svgs::component_name(&model.name))
+            .ok_or_else(|| ApiError::Internal(format!("Malformed SVG: {id} - {weight}")))?;
+
+        Ok(HttpResponse::Ok()
+            .content_type("text/plain; charset=utf-8")
+            .insert_header(http::header::CacheControl(vec![
+                http::header::CacheDirective::Public,
+                http::header::CacheDirective::MaxAge(SVG_MAX_AGE),
+            ]))
+            .body(component))
+    }
 
+    #[utoipa::path(
+        description = "Render a single weight of an icon to a square PNG, for use in emails and other contexts that don't support SVG.",
+        params(
+            ("id", example = 2884),
+            ("weight", example = "regular"),
+            db::RenderQuery,
+        ),
+        responses(
+            (status = OK, content_type = "image/png", body = Vec<u8>, description = "Rendered icon"),
+            (status = BAD_REQUEST, body = ErrorResponse, description = "Invalid weight or size"),
+            (status = NOT_FOUND, body = ErrorResponse, description = "Icon or weight not found"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
     )]
-    #[get("/categories")]
-    #[tracing::instrument(level = "info")]
-    async fn categories() -> impl Responder {
-        HttpResponse::Ok().json(CategoriesResponse {
-            categories: icons::Category::ALL.to_vec(),
-            count: icons::Category::COUNT,
-        })
+    #[get("/icon/{id}/{weight}.png")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn icon_png(
+        data: web::Data<app::AppState>,
+        path: web::Path<(i32, String)>,
+        query: web::Query<db::RenderQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        let (id, weight) = path.into_inner();
+        let size = query.size().map_err(ApiError::BadRequest)?;
+        let requested = icons::IconWeight::from_str(&weight).map_err(ApiError::BadRequest)?;
+        let weight = served_weight(&data, id, &requested, query.strict).await?;
+        let png = data
+            .png(id, &weight, size)
+            .await
+            .map_err(|e| ApiError::Internal(e.to_string()))?
+            .ok_or_else(|| ApiError::NotFound(format!("SVG not found: {id} - {weight}")))?;
+
+        let mut res = HttpResponse::Ok();
+        res.content_type("image/png")
+            .insert_header(weight_cache_control(&requested, &weight));
+        if query.strict == Some(false) {
+            res.insert_header((WEIGHT_SERVED, weight.to_string()));
+        }
+        Ok(res.body(png))
     }
 
-    #[derive(Serialize, ToSchema)]
-    struct TagsResponse {
-        tags: Vec<String>,
+    const SVG_MAX_AGE: u32 = 60 * 60 * 24 * 30;
+
+    /// Builds the full icon response, short-circuiting with `304 Not Modified` before it is
+    /// serialized when the client already holds the current version.
+    async fn single_icon_response(
+        req: &HttpRequest,
+        data: &app::AppState,
+        model: entities::icons::Model,
+        encoding: db::SvgEncoding,
+    ) -> Result<HttpResponse, ApiError> {
+        let svgmap = data.db.get_svg_weights_by_icon_id(model.id).await?;
+        let as_jsonapi = jsonapi::is_requested(req);
+        let etag = single_icon_etag(&model, &svgmap, as_jsonapi, encoding)?;
+        if let Some(mut res) = conditional::not_modified(req, &etag) {
+            vary_on_accept(&mut res);
+            return Ok(res);
+        }
+
+        let single = SingleIconResponse::new(model, svgmap, encoding);
+        let mut res = if as_jsonapi {
+            let mut resource = single.icon.to_resource();
+            let svgs = serde_json::to_value(&single.svgs)
+                .map_err(|e| ApiError::Internal(format!("Failed to serialize SVGs: {e}")))?;
+            resource.attributes.insert("svgs".to_string(), svgs);
+            jsonapi::JsonApi(jsonapi::Document::new(resource)).respond_to(req)
+        } else {
+            HttpResponse::Ok().json(single)
+        };
+        if let Ok((name, value)) = http::header::ETag(etag).try_into_pair() {
+            res.headers_mut().insert(name, value);
+        }
+        vary_on_accept(&mut res);
+        Ok(res)
+    }
+
+    /// The ETag of a single icon response, derived from every metadata field and SVG so that admin
+    /// edits and asset syncs invalidate it even without a `last_updated_at` bump.
+    fn single_icon_etag(
+        model: &entities::icons::Model,
+        svgmap: &HashMap<icons::IconWeight, svgs::Svg>,
+        as_jsonapi: bool,
+        encoding: db::SvgEncoding,
+    ) -> Result<http::header::EntityTag, ApiError> {
+        let metadata = serde_json::to_string(&icons::Icon::from(model.clone()))
+            .map_err(|e| ApiError::Internal(format!("Failed to serialize icon: {e}")))?;
+        let svgs = icons::IconWeight::ALL
+            .iter()
+            .map(|weight| svgmap.get(weight).map(|svg| svg.src.as_str()))
+            .collect::<Vec<_>>();
+        Ok(conditional::etag(&(metadata, svgs, as_jsonapi, encoding)))
+    }
+
+    /// Responses that switch to JSON:API on `Accept` must say so, so that shared caches keep the
+    /// two representations apart.
+    fn vary_on_accept(res: &mut HttpResponse) {
+        res.headers_mut().insert(
+            http::header::VARY,
+            http::header::HeaderValue::from_static("accept"),
+        );
+    }
+
+    /// Whether the client asked for CSV, with `format=csv` or `Accept: text/csv`.
+    fn csv_requested(req: &HttpRequest, format: &db::FormatQuery) -> bool {
+        match format.format {
+            Some(format) => format == db::ExportFormat::Csv,
+            None => req
+                .headers()
+                .get(http::header::ACCEPT)
+                .and_then(|h| h.to_str().ok())
+                .is_some_and(|accept| accept.contains("text/csv")),
+        }
+    }
+
+    /// Reports the number of icons matching a bulk export, which is more than the export includes
+    /// when it was cut short by a `limit` or `offset`.
+    const TOTAL_COUNT: &str = "X-Total-Count";
+
+    /// The number of icons matching a bulk export that included `included` of them.
+    async fn bulk_total(
+        data: &app::AppState,
+        query: &db::IconQuery,
+        included: usize,
+    ) -> Result<u64, ApiError> {
+        if query.is_partial() {
+            Ok(data.db.count_icons(query).await?)
+        } else {
+            Ok(included as u64)
+        }
+    }
+
+    fn csv_response(icons: &[icons::Icon], total: u64) -> Result<HttpResponse, ApiError> {
+        let body = export::icons_to_csv(icons)
+            .map_err(|e| ApiError::Internal(format!("Failed to write CSV: {e}")))?;
+        Ok(HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header((TOTAL_COUNT, total))
+            .insert_header(http::header::ContentDisposition::attachment("icons.csv"))
+            .insert_header((http::header::VARY, "accept"))
+            .body(body))
+    }
+
+    /// Respond with a page of icons, as a [`MultipleIconResponse`] or, when requested, a JSON:API
+    /// document with the paging details in `meta`.
+    fn icon_list_response(
+        req: &HttpRequest,
+        icons: Vec<icons::Icon>,
+        total: u64,
+        limit: u64,
+        cursor: Option<db::Cursor>,
+    ) -> HttpResponse {
+        let mut res = if jsonapi::is_requested(req) {
+            let resources = icons
+                .iter()
+                .map(ToResource::to_resource)
+                .collect::<Vec<_>>();
+            let mut document = jsonapi::Document::new(resources)
+                .meta("count", icons.len())
+                .meta("total", total)
+                .meta("limit", limit);
+            if let Some(cursor) = &cursor {
+                document = document.meta("next_cursor", cursor.encode());
+            }
+            jsonapi::JsonApi(document).respond_to(req)
+        } else {
+            HttpResponse::Ok().json(
+                MultipleIconResponse::new(icons)
+                    .total(total)
+                    .limit(limit)
+                    .next_cursor(cursor),
+            )
+        };
+        vary_on_accept(&mut res);
+        res
+    }
+
+    #[derive(ToSchema, Serialize)]
+    pub struct BatchIconResponse {
+        /// Found icons keyed by ID. Requested IDs that do not exist are omitted.
+        icons: HashMap<i32, SingleIconResponse>,
+        /// The number of icons in this response.
         count: usize,
     }
 
     #[utoipa::path(
-        description = "Fetch all unique icon tags from our database. These can be used as the `tags` parameter in the [/v1/icons](#tag/default/GET/v1/icons) endpoint.",
+        description = "Fetch several icons by ID in a single request, returning each icon's metadata and SVG code keyed by ID. At most 200 IDs may be requested at once.",
+        request_body = db::IconBatch,
         responses(
-            (status = OK, body = TagsResponse),
-            (status = INTERNAL_SERVER_ERROR, description = "Internal server error"),
+            (status = OK, body = BatchIconResponse),
+            (status = BAD_REQUEST, body = ErrorResponse, description = "Too many IDs requested"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
         ),
-        tag = "Metadata endpoints",
+        tag = "Icon endpoints",
     )]
-    #[get("/tags")]
-    #[tracing::instrument(level = "info")]
-    async fn tags(data: web::Data<app::AppState>) -> impl Responder {
-        match data.db.get_all_tags().await {
-            Ok(tags) => {
-                let count = tags.len();
-                HttpResponse::Ok().json(TagsResponse { tags, count })
-            }
-            Err(_) => {
-                tracing::error!("Failed to fetch tags");
-                HttpResponse::InternalServerError().finish()
-            }
+    #[post("/icons/batch")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn batch_icons(
+        data: web::Data<app::AppState>,
+        body: web::Json<db::IconBatch>,
+    ) -> Result<HttpResponse, ApiError> {
+        let db::IconBatch { mut ids } = body.into_inner();
+        ids.sort_unstable();
+        ids.dedup();
+        if ids.len() > db::IconBatch::MAX_IDS {
+            return Err(ApiError::BadRequest(format!(
+                "Too many IDs requested: {} (max {})",
+                ids.len(),
+                db::IconBatch::MAX_IDS
+            )));
         }
-    }
-}
 
-mod health {
-    use super::*;
-    use utoipa::ToSchema;
+        let (models, mut svgmaps) = tokio::try_join!(
+            data.db.get_icons_by_ids(&ids),
+            data.db.get_svg_weights_by_icon_ids(&ids),
+        )?;
+        let icons = models
+            .into_iter()
+            .map(|model| {
+                let svgmap = svgmaps.remove(&model.id).unwrap_or_default();
+                (
+                    model.id,
+                    SingleIconResponse::new(model, svgmap, db::SvgEncoding::Raw),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        let count = icons.len();
+        Ok(HttpResponse::Ok().json(BatchIconResponse { icons, count }))
+    }
 
-    #[derive(Serialize, ToSchema)]
-    #[serde(rename_all = "snake_case")]
-    enum HealthStatus {
-        Healthy,
-        Degraded,
-        Down,
+    #[derive(ToSchema, Serialize)]
+    pub struct MultipleIconResponse {
+        icons: Vec<icons::Icon>,
+        /// The number of icons in this response.
+        count: usize,
+        /// The total number of icons matching the query, across all pages.
+        total: u64,
+        /// The page size applied, after defaulting and clamping the requested `limit`. Absent
+        /// for unpaginated listings.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        limit: Option<u64>,
+        /// Pass as `cursor` to fetch the next page. Absent on the last page.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        next_cursor: Option<String>,
     }
 
-    #[derive(Serialize, ToSchema)]
-    struct HealthResponse {
-        status: HealthStatus,
+    impl MultipleIconResponse {
+        pub fn new(icons: Vec<icons::Icon>) -> Self {
+            let count = icons.len();
+            Self {
+                icons,
+                count,
+                total: count as u64,
+                limit: None,
+                next_cursor: None,
+            }
+        }
+
+        pub fn total(mut self, total: u64) -> Self {
+            self.total = total;
+            self
+        }
+
+        pub fn limit(mut self, limit: u64) -> Self {
+            self.limit = Some(limit);
+            self
+        }
+
+        pub fn next_cursor(mut self, cursor: Option<db::Cursor>) -> Self {
+            self.next_cursor = cursor.map(|c| c.encode());
+            self
+        }
     }
 
     #[utoipa::path(
-        description = "Reports the health of the API. Returns `healthy` if the database is reachable, `degraded` if there are issues, and `down` if the database is unreachable.",
+        description = "Fetch icons from our database, with optional query parameters to filter by name, status, release version, tags, and categories.",
+        params(db::IconQuery, db::FormatQuery),
         responses(
-            (
-                status = OK,
-                body = HealthResponse,
-                description = "Service is healthy",
-            ),
-            (
-                status = SERVICE_UNAVAILABLE,
-                body = HealthResponse,
-                example = json!(HealthResponse { status: HealthStatus::Down }),,
-                description = "Service is down, unreachable",
-            ),
-            (
-                status = INTERNAL_SERVER_ERROR,
-                body = HealthResponse,
-                example = json!(HealthResponse { status: HealthStatus::Degraded }),,
-                description = "Service is degraded, connected but unresponsive",
-            ),
+            (status = OK, body = MultipleIconResponse),
+            (status = OK, content_type = "text/csv", body = String, description = "With `format=csv` or `Accept: text/csv`: one row per icon, with columns `id,name,alias,code,status,categories,tags,released_at,published`. Every match is included unless a `limit` is given; the `X-Total-Count` header reports the number of matches."),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
         ),
-        tag = "Other endpoints",
+        tag = "Icon endpoints",
     )]
-    #[get("/health")]
-    #[tracing::instrument(level = "info")]
-    async fn health_check(data: web::Data<app::AppState>) -> impl Responder {
-        if let Err(e) = data.db.ping().await {
-            tracing::error!("Database ping failed: {e}");
-            return HttpResponse::InternalServerError().json(HealthResponse {
-                status: HealthStatus::Degraded,
-            });
+    #[get("/icons")]
+    #[tracing::instrument(level = "info", skip(req, data))]
+    async fn all_icons(
+        req: HttpRequest,
+        data: web::Data<app::AppState>,
+        query: QsQuery<db::IconQuery>,
+        format: web::Query<db::FormatQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        if csv_requested(&req, &format) {
+            let query = query.into_inner().bulk(&data.config.page_size);
+            let cached = query
+                .is_default_listing()
+                .then(|| data.cached_icons())
+                .flatten();
+            let (icons, total) = match cached {
+                Some(cached) => {
+                    let icons = cached
+                        .iter()
+                        .skip(query.offset.unwrap_or_default() as usize)
+                        .take(query.limit.map_or(usize::MAX, |limit| limit as usize))
+                        .cloned()
+                        .map(icons::Icon::from)
+                        .collect();
+                    (icons, cached.len() as u64)
+                }
+                None => {
+                    let icons = data
+                        .db
+                        .get_icons(&query)
+                        .await?
+                        .into_iter()
+                        .map(icons::Icon::from)
+                        .collect::<Vec<_>>();
+                    let total = bulk_total(&data, &query, icons.len()).await?;
+                    (icons, total)
+                }
+            };
+            return csv_response(&icons, total);
         }
 
-        HttpResponse::Ok().json(HealthResponse {
-            status: HealthStatus::Healthy,
-        })
-    }
+        let query = query.into_inner().paginated(&data.config.page_size);
+        query.validate_cursor().map_err(ApiError::BadRequest)?;
 
-    #[get("/dump")]
-    #[tracing::instrument(level = "info")]
-    pub async fn dump(data: web::Data<app::AppState>) -> impl Responder {
-        match data.db.dump_stats().await {
-            Ok(_) => HttpResponse::Ok().finish(),
-            Err(e) => {
-                tracing::error!("Failed to dump database: {e:?}");
-                HttpResponse::InternalServerError().finish()
+        if query.is_default_listing() {
+            if let Some(cached) = data.cached_icons() {
+                let start = (query.offset.unwrap_or_default() as usize).min(cached.len());
+                let end = start
+                    .saturating_add(query.limit.unwrap_or_default() as usize)
+                    .min(cached.len());
+                let page = &cached[start..end];
+                let cursor = (end < cached.len())
+                    .then(|| page.last().map(|last| db::Db::cursor_after(&query, last)))
+                    .flatten();
+                let icons = page
+                    .iter()
+                    .cloned()
+                    .map(icons::Icon::from)
+                    .collect::<Vec<_>>();
+                return Ok(icon_list_response(
+                    &req,
+                    icons,
+                    cached.len() as u64,
+                    query.limit.unwrap_or_default(),
+                    cursor,
+                ));
             }
         }
+
+        let ((icons, cursor), total) =
+            tokio::try_join!(data.db.get_icon_page(&query), data.db.count_icons(&query))?;
+        let icons = icons.into_iter().map(icons::Icon::from).collect::<Vec<_>>();
+        let limit = query.limit.unwrap_or_default();
+        Ok(icon_list_response(&req, icons, total, limit, cursor))
+    }
+
+    #[utoipa::path(
+        description = "Build an SVG sprite sheet of every icon matching the query, in a single weight. Each icon is a `<symbol>` with the ID `<name>-<weight>`, to be referenced with `<use href=\"#cube-regular\"/>`. Every match is included unless a `limit` is given; the `X-Total-Count` header reports the number of matches.",
+        params(db::IconQuery, db::WeightQuery),
+        responses(
+            (status = OK, content_type = "image/svg+xml", body = String, description = "Sprite sheet"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/sprite.svg")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn sprite(
+        data: web::Data<app::AppState>,
+        query: QsQuery<db::IconQuery>,
+        weight: web::Query<db::WeightQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        let query = query.into_inner().bulk(&data.config.page_size);
+        let weight = weight.into_inner().weight.unwrap_or_default();
+        let (body, included) = build_sprite(&data, &query, &weight).await?;
+        let total = bulk_total(&data, &query, included).await?;
+
+        Ok(HttpResponse::Ok()
+            .content_type("image/svg+xml")
+            .insert_header((TOTAL_COUNT, total))
+            .insert_header(http::header::CacheControl(vec![
+                http::header::CacheDirective::Public,
+                http::header::CacheDirective::MaxAge(SPRITE_MAX_AGE),
+            ]))
+            .body(body))
+    }
+
+    #[utoipa::path(
+        description = "Build an SVG sprite sheet of every published icon in a category, in a single weight, in the same format as [/v1/sprite.svg](#tag/icon-endpoints/GET/v1/sprite.svg). The category name is matched case-insensitively.",
+        params(
+            ("category", example = "arrows"),
+            db::WeightQuery,
+        ),
+        responses(
+            (status = OK, content_type = "image/svg+xml", body = String, description = "Sprite sheet"),
+            (status = NOT_MODIFIED, description = "Sprite unchanged since the given ETag"),
+            (status = NOT_FOUND, body = ErrorResponse, description = "Unknown category"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/category/{category}/sprite.svg")]
+    #[tracing::instrument(level = "info", skip(req, data))]
+    async fn category_sprite(
+        req: HttpRequest,
+        data: web::Data<app::AppState>,
+        category: web::Path<String>,
+        weight: web::Query<db::WeightQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        let category = category.into_inner();
+        let category = icons::Category::ALL
+            .into_iter()
+            .filter(|known| *known != icons::Category::Unknown)
+            .find(|known| known.to_string().eq_ignore_ascii_case(&category))
+            .ok_or_else(|| ApiError::NotFound(format!("Unknown category: {category}")))?;
+        let weight = weight.into_inner().weight.unwrap_or_default();
+
+        let query = db::IconQuery {
+            category: Some(vec![category]),
+            ..db::IconQuery::new()
+        }
+        .bulk(&data.config.page_size);
+        let (body, included) = build_sprite(&data, &query, &weight).await?;
+
+        let etag = conditional::etag(&body);
+        if let Some(res) = conditional::not_modified(&req, &etag) {
+            return Ok(res);
+        }
+
+        Ok(HttpResponse::Ok()
+            .content_type("image/svg+xml")
+            .insert_header((TOTAL_COUNT, included))
+            .insert_header(http::header::ETag(etag))
+            .insert_header(http::header::CacheControl(vec![
+                http::header::CacheDirective::Public,
+                http::header::CacheDirective::MaxAge(SPRITE_MAX_AGE),
+            ]))
+            .body(body))
+    }
+
+    /// A sprite sheet with a `<symbol>` for every icon matching the query that has an SVG in
+    /// `weight`, along with the number of icons the query returned.
+    async fn build_sprite(
+        data: &app::AppState,
+        query: &db::IconQuery,
+        weight: &icons::IconWeight,
+    ) -> Result<(String, usize), ApiError> {
+        let models = data.db.get_icons(query).await?;
+        let included = models.len();
+        let ids = models.iter().map(|model| model.id).collect::<Vec<_>>();
+        let mut svgmaps = data.db.get_svg_weights_by_icon_ids(&ids).await?;
+
+        let mut body = String::from(r#"<svg xmlns="http://www.w3.org/2000/svg">"#);
+        for model in models {
+            let Some(svg) = svgmaps
+                .get_mut(&model.id)
+                .and_then(|svgmap| svgmap.remove(weight))
+            else {
+                continue;
+            };
+            match svgs::to_symbol(&svg.src, &format!("{}-{weight}", model.name)) {
+                Some(symbol) => body.push_str(&symbol),
+                None => tracing::warn!("Skipping malformed SVG: {} - {}", model.name, weight),
+            }
+        }
+        body.push_str("</svg>");
+        Ok((body, included))
+    }
+
+    #[utoipa::path(
+        description = "Build a stylesheet of every icon matching the query, in a single weight. Each icon is a `.ph-<name>` class setting the SVG as a `background-image` data URI. Every match is included unless a `limit` is given; the `X-Total-Count` header reports the number of matches.",
+        params(db::IconQuery, db::WeightQuery),
+        responses(
+            (status = OK, content_type = "text/css", body = String, description = "Stylesheet"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/icons.css")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn stylesheet(
+        data: web::Data<app::AppState>,
+        query: QsQuery<db::IconQuery>,
+        weight: web::Query<db::WeightQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        let query = query.into_inner().bulk(&data.config.page_size);
+        let weight = weight.into_inner().weight.unwrap_or_default();
+
+        let models = data.db.get_icons(&query).await?;
+        let total = bulk_total(&data, &query, models.len()).await?;
+        let ids = models.iter().map(|model| model.id).collect::<Vec<_>>();
+        let mut svgmaps = data.db.get_svg_weights_by_icon_ids(&ids).await?;
+
+        let mut body = String::new();
+        for model in models {
+            if let Some(svg) = svgmaps
+                .get_mut(&model.id)
+                .and_then(|svgmap| svgmap.remove(&weight))
+            {
+                body.push_str(&svgs::to_css_rule(&svg.src, &format!("ph-{}", model.name)));
+            }
+        }
+
+        Ok(HttpResponse::Ok()
+            .content_type("text/css; charset=utf-8")
+            .insert_header((TOTAL_COUNT, total))
+            .insert_header(http::header::CacheControl(vec![
+                http::header::CacheDirective::Public,
+                http::header::CacheDirective::MaxAge(SPRITE_MAX_AGE),
+            ]))
+            .body(body))
+    }
+
+    #[utoipa::path(
+        description = "Download a ZIP archive of the SVGs for every icon matching the query, named `<name>-<weight>.svg`. Every match is included unless a `limit` is given; the `X-Total-Count` header reports the number of matches.",
+        params(db::IconQuery, db::DownloadQuery),
+        responses(
+            (status = OK, content_type = "application/zip", body = Vec<u8>, description = "ZIP archive"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/download.zip")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn download(
+        data: web::Data<app::AppState>,
+        query: QsQuery<db::IconQuery>,
+        download: web::Query<db::DownloadQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        let query = query.into_inner().bulk(&data.config.page_size);
+
+        let models = data.db.get_icons(&query).await?;
+        let total = bulk_total(&data, &query, models.len()).await?;
+        let ids = models.iter().map(|model| model.id).collect::<Vec<_>>();
+        let mut svgmaps = data.db.get_svg_weights_by_icon_ids(&ids).await?;
+
+        let mut files = Vec::new();
+        for model in models {
+            let Some(svgmap) = svgmaps.remove(&model.id) else {
+                continue;
+            };
+            for (weight, svg) in svgmap {
+                if download.weight.as_ref().is_none_or(|w| *w == weight) {
+                    files.push((format!("{}-{weight}.svg", model.name), svg.src));
+                }
+            }
+        }
+        files.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(HttpResponse::Ok()
+            .content_type("application/zip")
+            .insert_header((TOTAL_COUNT, total))
+            .insert_header(http::header::ContentDisposition::attachment(
+                "phosphor-icons.zip",
+            ))
+            .streaming(archive::stream_zip(files)))
+    }
+
+    /// Sprites and stylesheets change whenever a matching icon is added, so they are cached far
+    /// more briefly than individual SVGs.
+    const SPRITE_MAX_AGE: u32 = 60 * 60;
+
+    #[utoipa::path(
+        description = "Fetch the most recently released icons, newest first.",
+        params(db::LatestQuery),
+        responses(
+            (status = OK, body = MultipleIconResponse),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/icons/latest")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn latest_icons(
+        data: web::Data<app::AppState>,
+        query: web::Query<db::LatestQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        let limit = query.limit(&data.config.page_size);
+        let icons = data.db.get_latest_icons(limit).await?;
+        let icons = icons.into_iter().map(icons::Icon::from).collect::<Vec<_>>();
+        Ok(HttpResponse::Ok().json(MultipleIconResponse::new(icons).limit(limit)))
+    }
+
+    #[utoipa::path(
+        description = "An Atom feed of the most recently released icons, newest first. Each entry is titled with the icon name and release version, categorized by version, and links to the icon's regular-weight SVG.",
+        params(db::LatestQuery),
+        responses(
+            (status = OK, content_type = "application/atom+xml", body = String, description = "Atom feed"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/feed.xml")]
+    #[tracing::instrument(level = "info", skip(req, data))]
+    async fn atom_feed(
+        req: HttpRequest,
+        data: web::Data<app::AppState>,
+        query: web::Query<db::LatestQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        let models = data
+            .db
+            .get_latest_icons(query.limit(&data.config.page_size))
+            .await?;
+        let info = req.connection_info();
+        let base_url = format!("{}://{}", info.scheme(), info.host());
+        Ok(HttpResponse::Ok()
+            .content_type("application/atom+xml; charset=utf-8")
+            .body(feed::atom(&models, &base_url, chrono::Utc::now())))
+    }
+
+    #[utoipa::path(
+        description = "Fetch random published icons matching the query, e.g. to showcase in a demo. Fewer than `count` icons are returned when fewer match, and none when nothing does.",
+        params(db::IconQuery, db::RandomQuery),
+        responses(
+            (status = OK, body = MultipleIconResponse, description = "Random icons"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/icons/random")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn random_icons(
+        data: web::Data<app::AppState>,
+        query: QsQuery<db::IconQuery>,
+        random: web::Query<db::RandomQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        let count = random.count(&data.config.page_size);
+        let models = data.db.get_random_icons(&query, count).await?;
+        let icons = models
+            .into_iter()
+            .map(icons::Icon::from)
+            .collect::<Vec<_>>();
+        Ok(HttpResponse::Ok()
+            .insert_header(http::header::CacheControl(vec![
+                http::header::CacheDirective::NoStore,
+            ]))
+            .json(MultipleIconResponse::new(icons).limit(count)))
+    }
+
+    #[derive(ToSchema, Serialize)]
+    pub struct ScoredIcon {
+        #[serde(flatten)]
+        icon: icons::Icon,
+        /// The relevance of the icon to the search term, from `0.0` to `1.0`.
+        #[schema(example = 0.42)]
+        score: f64,
+        /// Where the search matched, when requested with `highlight=true`. Omitted for icons that
+        /// only matched approximately.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        highlight: Option<icons::Highlight>,
+    }
+
+    #[derive(ToSchema, Serialize)]
+    pub struct SearchIconResponse {
+        /// Matching icons, most relevant first.
+        icons: Vec<ScoredIcon>,
+        /// The number of icons in this response.
+        count: usize,
+        /// The total number of icons matching the search, across all pages.
+        total: u64,
+        /// The page size applied, after defaulting and clamping the requested `limit`.
+        limit: u64,
+    }
+
+    impl SearchIconResponse {
+        pub fn new(icons: Vec<ScoredIcon>, total: u64, limit: u64) -> Self {
+            let count = icons.len();
+            Self {
+                icons,
+                count,
+                total,
+                limit,
+            }
+        }
+    }
+
+    #[utoipa::path(
+        description = "Fuzzy search for icons by semantic name, use-case, or other properties. Returns a page of results, most relevant first, along with a relevance score and the total number of matches.",
+        params(db::IconSearch),
+        responses(
+            (status = OK, body = SearchIconResponse),
+            (status = BAD_REQUEST, body = ErrorResponse, description = "Threshold out of range"),
+            (status = NOT_FOUND, body = ErrorResponse, description = "Icon not found"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/search")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn search_icons(
+        data: web::Data<app::AppState>,
+        search: web::Query<db::IconSearch>,
+    ) -> Result<HttpResponse, ApiError> {
+        let search = search.into_inner();
+        search.validate().map_err(ApiError::BadRequest)?;
+        let terms = search.highlight.unwrap_or_default().then(|| {
+            let mut terms = data.synonyms().expand(&search.q);
+            terms.insert(0, search.q.clone());
+            terms
+        });
+        let (results, total) = data.search(&search).await?;
+        let icons = results
+            .into_iter()
+            .map(|r| ScoredIcon {
+                highlight: terms
+                    .as_ref()
+                    .and_then(|terms| icons::Highlight::find(&r.icon, terms)),
+                icon: icons::Icon::from(r.icon),
+                score: r.score,
+            })
+            .collect::<Vec<_>>();
+        let limit = data.config.page_size.clamp_limit(search.limit);
+        Ok(HttpResponse::Ok().json(SearchIconResponse::new(icons, total, limit)))
+    }
+
+    #[derive(ToSchema, Serialize)]
+    pub struct AutocompleteResponse {
+        /// Matching icons, ordered by name.
+        icons: Vec<icons::IconName>,
+        count: usize,
+        /// The most suggestions that could have been returned, after defaulting and clamping the
+        /// requested `limit`.
+        limit: u64,
+    }
+
+    #[utoipa::path(
+        description = "Suggest published icons whose name or alias starts with a prefix, for search-as-you-type inputs. Only IDs and names are returned; fetch the full icon with `/v1/icon/{id}`.",
+        params(db::AutocompleteQuery),
+        responses(
+            (status = OK, body = AutocompleteResponse),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/autocomplete")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn autocomplete(
+        data: web::Data<app::AppState>,
+        query: web::Query<db::AutocompleteQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        let suggestions = data.db.autocomplete(&query).await?;
+        Ok(HttpResponse::Ok().json(AutocompleteResponse {
+            count: suggestions.len(),
+            icons: suggestions,
+            limit: query.limit(&data.config.page_size),
+        }))
+    }
+
+    #[derive(ToSchema, Serialize)]
+    pub struct DeprecatedIcon {
+        #[serde(flatten)]
+        icon: icons::Icon,
+        /// The kebab-case name of the icon to use instead, resolved by following icon aliases, or
+        /// `null` if there is none.
+        #[schema(example = "instagram")]
+        replacement: Option<String>,
+    }
+
+    #[derive(ToSchema, Serialize)]
+    pub struct DeprecatedIconsResponse {
+        /// Deprecated icons, most recently deprecated first.
+        icons: Vec<DeprecatedIcon>,
+        count: usize,
+    }
+
+    #[utoipa::path(
+        description = "List every icon with a deprecation version, most recently deprecated first, along with the icon that replaces it. Unlike `/v1/deprecations`, full icon metadata is returned, including unpublished icons.",
+        responses(
+            (status = OK, body = DeprecatedIconsResponse),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/deprecated")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn deprecated_icons(data: web::Data<app::AppState>) -> Result<HttpResponse, ApiError> {
+        let icons = data
+            .db
+            .get_deprecated_icons()
+            .await?
+            .into_iter()
+            .map(|(model, replacement)| DeprecatedIcon {
+                icon: icons::Icon::from(model),
+                replacement,
+            })
+            .collect::<Vec<_>>();
+        let count = icons.len();
+        Ok(HttpResponse::Ok().json(DeprecatedIconsResponse { icons, count }))
+    }
+
+    #[utoipa::path(
+        description = "Fetch a map of every published icon's kebab-case name to its decimal font codepoint, for use in font generation pipelines. Icons without a codepoint are left out.",
+        responses(
+            (
+                status = OK,
+                body = std::collections::BTreeMap<String, i32>,
+                example = json!({ "cube": 57818, "cube-focus": 58024 }),
+            ),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/codepoints")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn codepoints(data: web::Data<app::AppState>) -> Result<HttpResponse, ApiError> {
+        Ok(HttpResponse::Ok().json(data.db.get_codepoints().await?))
+    }
+
+    #[derive(ToSchema, Serialize)]
+    pub struct DeprecationsResponse {
+        deprecations: Vec<icons::Deprecation>,
+        count: usize,
+    }
+
+    #[utoipa::path(
+        description = "Fetch every deprecated icon along with the name of the icon that replaces it, if any. Replacements are resolved by following icon aliases, and can be used as a rename map when migrating between library versions.",
+        responses(
+            (status = OK, body = DeprecationsResponse),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/deprecations")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn deprecations(data: web::Data<app::AppState>) -> Result<HttpResponse, ApiError> {
+        let deprecations = data.db.get_deprecations().await?;
+        let count = deprecations.len();
+        Ok(HttpResponse::Ok().json(DeprecationsResponse {
+            deprecations,
+            count,
+        }))
+    }
+
+    #[derive(ToSchema, Serialize)]
+    pub struct VersionChangesResponse {
+        /// The version the changes were computed for.
+        #[schema(example = 2.1f64)]
+        version: f64,
+        /// Icons first released in this version.
+        added: Vec<icons::Icon>,
+        /// Icons last updated in this version.
+        updated: Vec<icons::Icon>,
+        /// Icons deprecated in this version.
+        deprecated: Vec<icons::Icon>,
+    }
+
+    #[utoipa::path(
+        description = "Fetch the icons that were added, updated, or deprecated in a single `<major>.<minor>` library version, suitable for writing release notes.",
+        params(
+            ("version", example = "2.1"),
+        ),
+        responses(
+            (status = OK, body = VersionChangesResponse),
+            (status = BAD_REQUEST, body = ErrorResponse, description = "Invalid version"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/version/{version}/changes")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn version_changes(
+        data: web::Data<app::AppState>,
+        version: web::Path<String>,
+    ) -> Result<HttpResponse, ApiError> {
+        let version = version.into_inner();
+        let range = db::IconReleaseQuery::version(&version).map_err(ApiError::BadRequest)?;
+        let (added, updated, deprecated) = changes_in(&data, range).await?;
+        Ok(HttpResponse::Ok().json(VersionChangesResponse {
+            version: version.parse().unwrap_or_default(),
+            added,
+            updated,
+            deprecated,
+        }))
+    }
+
+    #[derive(ToSchema, Serialize)]
+    pub struct VersionDiffResponse {
+        #[schema(example = 2.0f64)]
+        from: f64,
+        #[schema(example = 2.1f64)]
+        to: f64,
+        /// Icons first released between the two versions.
+        added: Vec<icons::Icon>,
+        /// Icons last updated between the two versions.
+        updated: Vec<icons::Icon>,
+        /// Icons deprecated between the two versions.
+        deprecated: Vec<icons::Icon>,
+    }
+
+    #[utoipa::path(
+        description = "Fetch the icons that were added, updated, or deprecated between two `<major>.<minor>` library versions, inclusive of both, suitable for changelogs spanning several releases.",
+        params(db::DiffQuery),
+        responses(
+            (status = OK, body = VersionDiffResponse),
+            (status = BAD_REQUEST, body = ErrorResponse, description = "Invalid version, or `from` is after `to`"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/diff")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn version_diff(
+        data: web::Data<app::AppState>,
+        query: web::Query<db::DiffQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        let range =
+            db::IconReleaseQuery::between(&query.from, &query.to).map_err(ApiError::BadRequest)?;
+        let (added, updated, deprecated) = changes_in(&data, range).await?;
+        Ok(HttpResponse::Ok().json(VersionDiffResponse {
+            from: query.from.parse().unwrap_or_default(),
+            to: query.to.parse().unwrap_or_default(),
+            added,
+            updated,
+            deprecated,
+        }))
+    }
+
+    /// Icons added, updated, and deprecated within `range`, published or not. Changes after the
+    /// configured current version are left out.
+    async fn changes_in(
+        data: &app::AppState,
+        range: db::IconReleaseQuery,
+    ) -> Result<(Vec<icons::Icon>, Vec<icons::Icon>, Vec<icons::Icon>), ApiError> {
+        let range = data.db.until_current_version(range);
+        let added = db::IconQuery::new()
+            .published(db::Ternary::Any)
+            .released(range.clone());
+        let updated = db::IconQuery::new()
+            .published(db::Ternary::Any)
+            .updated(range.clone());
+        let deprecated = db::IconQuery::new()
+            .published(db::Ternary::Any)
+            .deprecated(range);
+
+        let (added, updated, deprecated) = tokio::try_join!(
+            data.db.get_icons(&added),
+            data.db.get_icons(&updated),
+            data.db.get_icons(&deprecated),
+        )?;
+        let into_icons = |models: Vec<entities::icons::Model>| {
+            models
+                .into_iter()
+                .map(icons::Icon::from)
+                .collect::<Vec<_>>()
+        };
+        Ok((
+            into_icons(added),
+            into_icons(updated),
+            into_icons(deprecated),
+        ))
+    }
+
+    #[derive(ToSchema, Serialize)]
+    pub struct PipelineStage {
+        /// The design status shared by every icon in this stage.
+        #[schema(example = "Designing")]
+        status: icons::IconStatus,
+        count: usize,
+        icons: Vec<icons::Icon>,
+    }
+
+    #[derive(ToSchema, Serialize)]
+    pub struct PipelineResponse {
+        /// Unpublished icons grouped by status, in design pipeline order.
+        stages: Vec<PipelineStage>,
+        count: usize,
+    }
+
+    #[utoipa::path(
+        description = "Fetch all unpublished icons grouped by their status in the design pipeline (Backlog → Designing → Designed → Implemented), along with a count for each stage.",
+        responses(
+            (status = OK, body = PipelineResponse),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/pipeline")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn pipeline(data: web::Data<app::AppState>) -> Result<HttpResponse, ApiError> {
+        let query = db::IconQuery::new().published(db::Ternary::False);
+        let models = data.db.get_icons(&query).await?;
+        let count = models.len();
+        let mut by_status = HashMap::<icons::IconStatus, Vec<icons::Icon>>::new();
+        for model in models.into_iter().map(icons::Icon::from) {
+            by_status
+                .entry(model.status.clone())
+                .or_default()
+                .push(model);
+        }
+        let stages = icons::IconStatus::ALL
+            .into_iter()
+            .map(|status| {
+                let icons = by_status.remove(&status).unwrap_or_default();
+                PipelineStage {
+                    status,
+                    count: icons.len(),
+                    icons,
+                }
+            })
+            .collect();
+        Ok(HttpResponse::Ok().json(PipelineResponse { stages, count }))
+    }
+
+    #[utoipa::path(
+        description = "List icons that are planned or being designed (`Backlog`, `Designing`, or `Designed`), ordered by how far along the pipeline they are and then by name. These icons are unpublished, and most have no SVGs yet.",
+        responses(
+            (status = OK, body = MultipleIconResponse),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Icon endpoints",
+    )]
+    #[get("/upcoming")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn upcoming(data: web::Data<app::AppState>) -> Result<HttpResponse, ApiError> {
+        let query = db::IconQuery::new()
+            .published(db::Ternary::Any)
+            .status(icons::IconStatus::UPCOMING.to_vec());
+        let mut icons = data
+            .db
+            .get_icons(&query)
+            .await?
+            .into_iter()
+            .map(icons::Icon::from)
+            .collect::<Vec<_>>();
+        // Icons come back ordered by name, which the stable sort preserves within each status.
+        icons.sort_by_key(|upcoming| {
+            icons::IconStatus::UPCOMING
+                .iter()
+                .position(|status| *status == upcoming.status)
+        });
+        Ok(HttpResponse::Ok().json(MultipleIconResponse::new(icons)))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn model() -> entities::icons::Model {
+            entities::icons::Model {
+                id: 2884,
+                rid: "96cR4kqjHO16pBVCiXg_Ep".to_string(),
+                name: "cube".to_string(),
+                status: "Implemented".to_string(),
+                category: "Design".to_string(),
+                search_categories: vec!["Design".to_string()],
+                tags: vec!["box".to_string()],
+                notes: None,
+                released_at: Some(1.0),
+                last_updated_at: Some(1.0),
+                deprecated_at: None,
+                published: true,
+                alias: None,
+                code: Some(57818),
+            }
+        }
+
+        fn svgmap(src: &str) -> HashMap<icons::IconWeight, svgs::Svg> {
+            HashMap::from([(
+                icons::IconWeight::Bold,
+                svgs::Svg {
+                    id: 1,
+                    icon_id: 2884,
+                    weight: icons::IconWeight::Bold,
+                    src: src.to_string(),
+                },
+            )])
+        }
+
+        #[test]
+        fn single_icon_etag_changes_with_svgs() {
+            let etag =
+                |svgmap| single_icon_etag(&model(), &svgmap, false, db::SvgEncoding::Raw).unwrap();
+            let original = etag(svgmap(r#"<svg viewBox="0 0 256 256"/>"#));
+            assert_eq!(original, etag(svgmap(r#"<svg viewBox="0 0 256 256"/>"#)));
+            assert_ne!(
+                original,
+                etag(svgmap(r#"<svg viewBox="0 0 256 256"><path/></svg>"#))
+            );
+            assert_ne!(original, etag(HashMap::new()));
+        }
+
+        #[test]
+        fn etags_use_a_fixed_algorithm() {
+            // The first half of the SHA-256 of `cube` followed by the 0xff that `str` hashing
+            // appends.
+            assert_eq!(
+                conditional::etag(&"cube").tag(),
+                "5071c0ab4c108a66024013ff33c0b598"
+            );
+        }
+
+        #[test]
+        fn substituted_weights_are_not_cached() {
+            let cache_control =
+                |requested, served| weight_cache_control(&requested, &served).to_string();
+            assert_eq!(
+                cache_control(icons::IconWeight::Bold, icons::IconWeight::Bold),
+                format!("public, max-age={SVG_MAX_AGE}")
+            );
+            assert_eq!(
+                cache_control(icons::IconWeight::Duotone, icons::IconWeight::Regular),
+                "no-store"
+            );
+        }
+    }
+}
+
+mod metadata {
+    use super::*;
+    use phosphor_server::{db, icons};
+    use utoipa::ToSchema;
+
+    #[utoipa::path(
+        description = "Describe the current state of the library, including the most recent version and the number of icons.",
+        responses(
+            (status = OK, description = "LibraryInfo", body = icons::LibraryInfo),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Metadata endpoints",
+    )]
+    #[get("/info")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn info(data: web::Data<app::AppState>) -> Result<HttpResponse, ApiError> {
+        let info = data.db.get_library_info().await?;
+        Ok(HttpResponse::Ok().json(info))
+    }
+
+    #[derive(Serialize, ToSchema)]
+    struct CategoriesResponse {
+        categories: Vec<icons::Category>,
+        count: usize,
+    }
+
+    #[derive(Serialize, ToSchema)]
+    struct CategoryCountsResponse {
+        categories: Vec<icons::CategoryCount>,
+        count: usize,
+    }
+
+    #[utoipa::path(
+        description = "Fetch all icon categories from our database. These can be used as the `category` parameter in the [/v1/icons](#tag/icon-endpoints/GET/v1/icons) endpoint. Pass `with_counts=true` to include the number of published icons in each category.",
+        params(db::CategoriesQuery),
+        responses(
+            (status = OK, body = CategoriesResponse),
+            (status = OK, body = CategoryCountsResponse, description = "When `with_counts=true`"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Metadata endpoints",
+    )]
+    #[get("/categories")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn categories(
+        data: web::Data<app::AppState>,
+        query: web::Query<db::CategoriesQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        if query.with_counts.unwrap_or_default() {
+            let category_counts = data.db.get_category_counts().await?;
+            return Ok(HttpResponse::Ok().json(CategoryCountsResponse {
+                count: category_counts.len(),
+                categories: category_counts,
+            }));
+        }
+
+        Ok(HttpResponse::Ok().json(CategoriesResponse {
+            categories: icons::Category::ALL.to_vec(),
+            count: icons::Category::COUNT,
+        }))
+    }
+
+    #[derive(Serialize, ToSchema)]
+    struct TagsResponse {
+        tags: Vec<String>,
+        count: usize,
+    }
+
+    #[derive(Serialize, ToSchema)]
+    struct TagCountsResponse {
+        /// Tags with the number of icons carrying each, sorted by descending count.
+        tags: Vec<icons::TagCount>,
+        count: usize,
+    }
+
+    #[utoipa::path(
+        description = "Fetch all unique icon tags from our database. These can be used as the `tags` parameter in the [/v1/icons](#tag/default/GET/v1/icons) endpoint. Pass `with_counts=true` to include the number of icons carrying each tag.",
+        params(db::TagsQuery),
+        responses(
+            (status = OK, body = TagsResponse),
+            (status = OK, body = TagCountsResponse, description = "When `with_counts=true`"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Metadata endpoints",
+    )]
+    #[get("/tags")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn tags(
+        data: web::Data<app::AppState>,
+        query: web::Query<db::TagsQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        if query.with_counts.unwrap_or_default() {
+            let tag_counts = data.db.get_tag_counts().await?;
+            let count = tag_counts.len();
+            return Ok(HttpResponse::Ok().json(TagCountsResponse {
+                tags: tag_counts,
+                count,
+            }));
+        }
+
+        let all_tags = data.db.get_all_tags().await?;
+        let count = all_tags.len();
+        Ok(HttpResponse::Ok().json(TagsResponse {
+            tags: all_tags,
+            count,
+        }))
+    }
+
+    #[derive(Serialize, ToSchema)]
+    struct FiltersResponse {
+        /// Values accepted by the `category` parameter.
+        categories: Vec<icons::Category>,
+        /// Sections of the Figma library, for display only.
+        figma_categories: Vec<icons::FigmaCategory>,
+        /// Values accepted by the `status` parameter.
+        statuses: Vec<icons::IconStatus>,
+        /// Available icon weights.
+        weights: Vec<icons::IconWeight>,
+        /// Values accepted by the `tags` parameter, with the number of icons carrying each, sorted
+        /// by descending count.
+        tags: Vec<icons::TagCount>,
+    }
+
+    #[utoipa::path(
+        description = "Fetch every valid filter value in a single call, including categories, Figma categories, statuses, weights, and tags with their icon counts. The response is cached until the catalog changes, and carries an `ETag` that honors `If-None-Match`.",
+        responses(
+            (status = OK, body = FiltersResponse),
+            (status = NOT_MODIFIED, description = "Filters unchanged since the given ETag"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Metadata endpoints",
+    )]
+    #[get("/filters")]
+    #[tracing::instrument(level = "info", skip(req, data))]
+    async fn filters(
+        req: HttpRequest,
+        data: web::Data<app::AppState>,
+    ) -> Result<HttpResponse, ApiError> {
+        let json = match data.cached_filters() {
+            Some(json) => json,
+            None => {
+                let body = FiltersResponse {
+                    categories: icons::Category::ALL.to_vec(),
+                    figma_categories: icons::FigmaCategory::ALL.to_vec(),
+                    statuses: icons::IconStatus::ALL.to_vec(),
+                    weights: icons::IconWeight::ALL.to_vec(),
+                    tags: data.db.get_tag_counts().await?,
+                };
+                let json = serde_json::to_vec(&body)
+                    .map(web::Bytes::from)
+                    .map_err(|e| ApiError::Internal(format!("Failed to serialize filters: {e}")))?;
+                data.cache_filters(json.clone());
+                json
+            }
+        };
+
+        let etag = conditional::etag(&json);
+        if let Some(res) = conditional::not_modified(&req, &etag) {
+            return Ok(res);
+        }
+
+        Ok(HttpResponse::Ok()
+            .insert_header(http::header::ETag(etag))
+            .content_type(http::header::ContentType::json())
+            .body(json))
+    }
+}
+
+mod conditional {
+    use actix_web::{
+        http::header::{ETag, EntityTag, Header, IfNoneMatch},
+        HttpRequest, HttpResponse,
+    };
+    use sha2::{Digest, Sha256};
+    use std::hash::{Hash, Hasher};
+
+    /// Feeds a value's [`Hash`] output into SHA-256. Unlike `DefaultHasher`, whose algorithm may
+    /// change between Rust releases, this keeps ETags stable across builds and server instances.
+    struct Sha256Hasher(Sha256);
+
+    impl Hasher for Sha256Hasher {
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.update(bytes);
+        }
+
+        fn finish(&self) -> u64 {
+            let digest = self.0.clone().finalize();
+            u64::from_be_bytes(
+                digest[..8]
+                    .try_into()
+                    .expect("SHA-256 digests are 32 bytes"),
+            )
+        }
+    }
+
+    /// Derives a weak ETag from any hashable representation of a response.
+    pub fn etag(value: &impl Hash) -> EntityTag {
+        let mut hasher = Sha256Hasher(Sha256::new());
+        value.hash(&mut hasher);
+        let digest = hasher.0.finalize();
+        EntityTag::new_weak(
+            digest[..16]
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect(),
+        )
+    }
+
+    /// Returns a `304 Not Modified` response if the request's `If-None-Match` header matches
+    /// `etag`, so handlers can bail out before building the full body.
+    pub fn not_modified(req: &HttpRequest, etag: &EntityTag) -> Option<HttpResponse> {
+        let matches = IfNoneMatch::parse(req).ok().is_some_and(|inm| match inm {
+            IfNoneMatch::Any => true,
+            IfNoneMatch::Items(items) => items.iter().any(|item| item.weak_eq(etag)),
+        });
+        matches.then(|| {
+            HttpResponse::NotModified()
+                .insert_header(ETag(etag.clone()))
+                .finish()
+        })
+    }
+}
+
+mod admin {
+    use super::*;
+    use phosphor_server::{auth::ApiKey, db, icons, renders, table};
+    use utoipa::ToSchema;
+
+    /// Large enough to accept a full catalog export in a single import request.
+    pub const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+    #[utoipa::path(
+        description = "Apply a partial update to an icon by its row ID. Only the fields present in the request body are changed; nullable fields can be cleared by setting them to `null`.",
+        params(
+            ("rid", example = "96cR4kqjHO16pBVCiXg_Ep"),
+        ),
+        request_body = db::IconPatch,
+        responses(
+            (status = OK, body = icons::Icon, description = "Icon updated"),
+            (status = UNAUTHORIZED, body = ErrorResponse, description = "Missing or invalid API key"),
+            (status = NOT_FOUND, body = ErrorResponse, description = "Icon not found"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Admin endpoints",
+    )]
+    #[patch("/icon/{rid}")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn patch_icon(
+        _key: ApiKey,
+        data: web::Data<app::AppState>,
+        rid: web::Path<String>,
+        body: web::Json<db::IconPatch>,
+    ) -> Result<HttpResponse, ApiError> {
+        let rid = rid.into_inner();
+        match data.db.patch_icon(&rid, body.into_inner()).await? {
+            Some(model) => {
+                data.refresh_icon_cache().await;
+                Ok(HttpResponse::Ok().json(icons::Icon::from(model)))
+            }
+            None => Err(ApiError::NotFound(format!("Icon not found: {rid}"))),
+        }
+    }
+
+    #[derive(Serialize, ToSchema)]
+    pub struct ImportRowResult {
+        /// The position of the record in the request body.
+        index: usize,
+        /// The record's `Row ID`, if it could be read.
+        rid: Option<String>,
+        /// Why the record was not imported, or `null` if it was.
+        error: Option<String>,
+    }
+
+    #[derive(Serialize, ToSchema)]
+    pub struct ImportResponse {
+        imported: usize,
+        failed: usize,
+        results: Vec<ImportRowResult>,
+    }
+
+    #[utoipa::path(
+        description = "Import a catalog dump as a JSON array of records in the same shape as the AppSheet `Icon Inventory` table. Each record is validated and upserted individually, and a per-record summary is returned.",
+        request_body(content = Vec<Object>, description = "AppSheet-shaped icon records"),
+        responses(
+            (status = OK, body = ImportResponse, description = "Import completed"),
+            (status = UNAUTHORIZED, body = ErrorResponse, description = "Missing or invalid API key"),
+        ),
+        tag = "Admin endpoints",
+    )]
+    #[post("/import")]
+    #[tracing::instrument(level = "info", skip(data, body))]
+    async fn import(
+        _key: ApiKey,
+        data: web::Data<app::AppState>,
+        body: web::Json<Vec<serde_json::Value>>,
+    ) -> Result<HttpResponse, ApiError> {
+        let mut results = Vec::new();
+        for (index, record) in body.into_inner().into_iter().enumerate() {
+            let rid = record
+                .get("Row ID")
+                .and_then(|v| v.as_str())
+                .map(str::to_owned);
+            let error = match serde_json::from_value::<table::TableIcon>(record) {
+                Ok(icon) => data.db.upsert_icon(icon.into()).await.err().map(|e| {
+                    tracing::error!("Failed to upsert imported icon {rid:?}: {e}");
+                    e.to_string()
+                }),
+                Err(e) => Some(e.to_string()),
+            };
+            results.push(ImportRowResult { index, rid, error });
+        }
+
+        let failed = results.iter().filter(|r| r.error.is_some()).count();
+        let imported = results.len() - failed;
+        tracing::info!("Imported {imported} icons, {failed} failed");
+        if imported > 0 {
+            data.refresh_icon_cache().await;
+        }
+        Ok(HttpResponse::Ok().json(ImportResponse {
+            imported,
+            failed,
+            results,
+        }))
+    }
+
+    #[derive(Serialize, ToSchema)]
+    pub struct SyncResponse {
+        /// Whether the AppSheet table had changed since the last sync.
+        changed: bool,
+        #[serde(flatten)]
+        report: app::SyncReport,
+        /// The number of terms in the reloaded synonym file, or `null` if it failed to load and
+        /// the previous synonyms were kept.
+        synonyms: Option<usize>,
+    }
+
+    #[utoipa::path(
+        description = "Pull the AppSheet icon table and upsert every icon, without restarting the server, and reload the search synonym file. The table sync is skipped if the table is unchanged since the last one.",
+        responses(
+            (status = OK, body = SyncResponse, description = "Sync completed"),
+            (status = UNAUTHORIZED, body = ErrorResponse, description = "Missing or invalid API key"),
+            (status = SERVICE_UNAVAILABLE, body = ErrorResponse, description = "A sync is already in progress"),
+            (status = INTERNAL_SERVER_ERROR, body = ErrorResponse, description = "Internal server error"),
+        ),
+        tag = "Admin endpoints",
+    )]
+    #[post("/sync")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn sync(_key: ApiKey, data: web::Data<app::AppState>) -> Result<HttpResponse, ApiError> {
+        match data.sync_table().await {
+            Ok(report) => Ok(HttpResponse::Ok().json(SyncResponse {
+                changed: report.is_some(),
+                report: report.unwrap_or_default(),
+                synonyms: data.reload_synonyms().await.ok(),
+            })),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                Err(ApiError::Unavailable(e.to_string()))
+            }
+            Err(e) => Err(ApiError::Internal(e.to_string())),
+        }
+    }
+
+    #[utoipa::path(
+        description = "Render every stored weight of every published icon into the in-memory render cache in the background, so the raw SVG and PNG endpoints serve them without hitting the database or the rasterizer. Poll the returned job at the `Location` header for progress. The cache is cleared by every sync and catalog edit.",
+        params(db::PrimeQuery),
+        responses(
+            (status = ACCEPTED, body = renders::PrimeStatus, description = "Priming started"),
+            (status = BAD_REQUEST, body = ErrorResponse, description = "Invalid format or size"),
+            (status = UNAUTHORIZED, body = ErrorResponse, description = "Missing or invalid API key"),
+            (status = SERVICE_UNAVAILABLE, body = ErrorResponse, description = "A priming job is already running"),
+        ),
+        tag = "Admin endpoints",
+    )]
+    #[post("/cache/prime")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn prime_cache(
+        _key: ApiKey,
+        data: web::Data<app::AppState>,
+        query: web::Query<db::PrimeQuery>,
+    ) -> Result<HttpResponse, ApiError> {
+        let size = query.size().map_err(ApiError::BadRequest)?;
+        match data.into_inner().prime_renders(query.formats(), size) {
+            Ok(job) => Ok(HttpResponse::Accepted()
+                .insert_header((
+                    http::header::LOCATION,
+                    format!("/admin/cache/prime/{}", job.id),
+                ))
+                .json(job.status())),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                Err(ApiError::Unavailable(e.to_string()))
+            }
+            Err(e) => Err(ApiError::Internal(e.to_string())),
+        }
+    }
+
+    #[utoipa::path(
+        description = "Read the progress of a cache-priming job. Only the most recent job is kept.",
+        params(
+            ("id", description = "The job ID returned when priming started"),
+        ),
+        responses(
+            (status = OK, body = renders::PrimeStatus, description = "Job progress"),
+            (status = UNAUTHORIZED, body = ErrorResponse, description = "Missing or invalid API key"),
+            (status = NOT_FOUND, body = ErrorResponse, description = "Job not found"),
+        ),
+        tag = "Admin endpoints",
+    )]
+    #[get("/cache/prime/{id}")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn prime_status(
+        _key: ApiKey,
+        data: web::Data<app::AppState>,
+        id: web::Path<uuid::Uuid>,
+    ) -> Result<HttpResponse, ApiError> {
+        let id = id.into_inner();
+        match data.prime_job(id) {
+            Some(job) => Ok(HttpResponse::Ok().json(job.status())),
+            None => Err(ApiError::NotFound(format!("Priming job not found: {id}"))),
+        }
+    }
+}
+
+mod migrate {
+    use migration::{Migrator, MigratorTrait};
+    use phosphor_server::{config::Config, db};
+
+    /// Apply every pending migration to the database at `DATABASE_URL`. Running against a fresh
+    /// database sets up the whole schema.
+    pub async fn run(config: &Config) -> Result<(), std::io::Error> {
+        let db = db::Db::init(config).await.map_err(|e| {
+            tracing::error!("Failed to connect to database: {e}");
+            std::io::Error::other(format!("Failed to connect to database: {e}"))
+        })?;
+
+        let pending = Migrator::get_pending_migrations(&db.conn)
+            .await
+            .map_err(|e| std::io::Error::other(format!("Failed to read migrations: {e}")))?;
+        tracing::info!("Applying {} pending migration(s)", pending.len());
+        Migrator::up(&db.conn, None).await.map_err(|e| {
+            tracing::error!("Migration failed: {e}");
+            std::io::Error::other(format!("Migration failed: {e}"))
+        })?;
+        tracing::info!("Database schema is up to date");
+
+        if let Err(e) = db.close().await {
+            tracing::warn!("Failed to close database connections: {e}");
+        }
+        Ok(())
+    }
+}
+
+mod timing {
+    use actix_web::{
+        body::MessageBody,
+        dev::{ServiceRequest, ServiceResponse},
+        http::header::{HeaderName, HeaderValue},
+        middleware::Next,
+    };
+    use std::time::Instant;
+
+    const SERVER_TIMING: HeaderName = HeaderName::from_static("server-timing");
+    const X_RESPONSE_TIME_MS: HeaderName = HeaderName::from_static("x-response-time-ms");
+
+    /// Reports the time spent handling each request via the `Server-Timing` and
+    /// `X-Response-Time-Ms` response headers, and records it as `duration_ms` on the request span
+    /// opened by [`super::request_id::propagate`].
+    pub async fn response_time(
+        req: ServiceRequest,
+        next: Next<impl MessageBody>,
+    ) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+        let start = Instant::now();
+        let mut res = next.call(req).await?;
+        let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+        tracing::Span::current().record("duration_ms", elapsed);
+
+        let headers = res.headers_mut();
+        if let Ok(value) = HeaderValue::from_str(&format!("total;dur={elapsed:.3}")) {
+            headers.insert(SERVER_TIMING, value);
+        }
+        if let Ok(value) = HeaderValue::from_str(&format!("{elapsed:.3}")) {
+            headers.insert(X_RESPONSE_TIME_MS, value);
+        }
+        Ok(res)
+    }
+}
+
+mod cors {
+    use actix_cors::Cors;
+
+    /// How long browsers may cache a preflight response, in seconds.
+    const MAX_AGE: usize = 3600;
+
+    /// Builds the CORS policy for the API from the configured allowed origins, where `*` allows
+    /// any origin. Preflight `OPTIONS` requests are answered by the middleware itself, allowing
+    /// any method and request header, and every response header is exposed to scripts.
+    pub fn policy(origins: &[String]) -> Cors {
+        let cors = Cors::default()
+            .allow_any_method()
+            .allow_any_header()
+            .expose_any_header()
+            .max_age(MAX_AGE);
+        if origins.iter().any(|origin| origin == "*") {
+            cors.allow_any_origin().send_wildcard()
+        } else {
+            origins
+                .iter()
+                .fold(cors, |cors, origin| cors.allowed_origin(origin))
+        }
+    }
+}
+
+mod caching {
+    use actix_web::{
+        body::MessageBody,
+        dev::{ServiceRequest, ServiceResponse},
+        http::{
+            header::{CacheControl, CacheDirective, TryIntoHeaderPair},
+            Method, StatusCode,
+        },
+        middleware::Next,
+        web,
+    };
+    use phosphor_server::{
+        app::AppState,
+        config::{DEFAULT_ICON_MAX_AGE, DEFAULT_QUERY_MAX_AGE},
+    };
+
+    /// Sets `Cache-Control` on successful `GET` responses under `/v1`, with a longer max-age for
+    /// single icons than for query results. Handlers that set their own `Cache-Control` (such as
+    /// SVGs and sprites) are left alone. `/health` is always `no-store`.
+    pub async fn cache_control(
+        req: ServiceRequest,
+        next: Next<impl MessageBody>,
+    ) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+        let (icon_max_age, query_max_age) = req
+            .app_data::<web::Data<AppState>>()
+            .map_or((DEFAULT_ICON_MAX_AGE, DEFAULT_QUERY_MAX_AGE), |state| {
+                (state.config.icon_max_age, state.config.query_max_age)
+            });
+        let path = req.path().to_owned();
+        let cacheable = req.method() == Method::GET;
+        let mut res = next.call(req).await?;
+
+        let directives = if path == "/health" {
+            vec![CacheDirective::NoStore]
+        } else if cacheable
+            && path.starts_with("/v1/")
+            && (res.status().is_success() || res.status() == StatusCode::NOT_MODIFIED)
+            && !res
+                .headers()
+                .contains_key(actix_web::http::header::CACHE_CONTROL)
+        {
+            let max_age = if path.starts_with("/v1/icon/") {
+                icon_max_age
+            } else {
+                query_max_age
+            };
+            vec![CacheDirective::Public, CacheDirective::MaxAge(max_age)]
+        } else {
+            return Ok(res);
+        };
+
+        if let Ok((name, value)) = CacheControl(directives).try_into_pair() {
+            res.headers_mut().insert(name, value);
+        }
+        Ok(res)
+    }
+}
+
+mod rate_limit {
+    use actix_web::{
+        body::{EitherBody, MessageBody},
+        dev::{ServiceRequest, ServiceResponse},
+        http::header::RETRY_AFTER,
+        middleware::Next,
+        web, ResponseError,
+    };
+    use phosphor_server::{app::AppState, error::ApiError};
+    use std::{net::IpAddr, time::Instant};
+
+    fn client_ip(req: &ServiceRequest, trust_proxy: bool) -> Option<IpAddr> {
+        if trust_proxy {
+            req.connection_info().realip_remote_addr().and_then(|addr| {
+                addr.parse::<IpAddr>().ok().or_else(|| {
+                    addr.parse::<std::net::SocketAddr>()
+                        .ok()
+                        .map(|addr| addr.ip())
+                })
+            })
+        } else {
+            req.peer_addr().map(|addr| addr.ip())
+        }
+    }
+
+    /// Token-bucket rate limiting keyed by client IP. Each client may make up to
+    /// `PHOSPHOR_RATE_LIMIT` requests per minute (600 by default), after which requests are
+    /// rejected with `429 Too Many Requests` and a `Retry-After` header until the bucket refills.
+    /// `/health` is exempt.
+    pub async fn limit(
+        req: ServiceRequest,
+        next: Next<impl MessageBody>,
+    ) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+        let Some(state) = req.app_data::<web::Data<AppState>>().cloned() else {
+            return Ok(next.call(req).await?.map_into_left_body());
+        };
+        let rpm = state.config.rate_limit;
+        let ip = match client_ip(&req, state.config.trust_proxy) {
+            Some(ip) if rpm > 0 && req.path() != "/health" => ip,
+            _ => return Ok(next.call(req).await?.map_into_left_body()),
+        };
+
+        match state.rate_limiter.take(ip, rpm, Instant::now()) {
+            Ok(()) => Ok(next.call(req).await?.map_into_left_body()),
+            Err(wait) => {
+                let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+                let mut res = ApiError::TooManyRequests(format!(
+                    "Rate limit of {rpm} requests per minute exceeded, retry in {retry_after}s"
+                ))
+                .error_response();
+                res.headers_mut().insert(RETRY_AFTER, retry_after.into());
+                Ok(req.into_response(res).map_into_right_body())
+            }
+        }
+    }
+}
+
+mod shutdown {
+    use actix_web::dev::ServerHandle;
+
+    async fn signal() -> &'static str {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            match signal(SignalKind::terminate()) {
+                Ok(mut sigterm) => tokio::select! {
+                    _ = tokio::signal::ctrl_c() => "SIGINT",
+                    _ = sigterm.recv() => "SIGTERM",
+                },
+                Err(e) => {
+                    tracing::warn!("Failed to install SIGTERM handler: {e}");
+                    let _ = tokio::signal::ctrl_c().await;
+                    "SIGINT"
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = tokio::signal::ctrl_c().await;
+            "Ctrl-C"
+        }
+    }
+
+    /// Waits for SIGINT or SIGTERM, then stops the servers gracefully: they stop accepting
+    /// connections and wait up to the shutdown `timeout`, in seconds, for in-flight requests to
+    /// complete, after which `main` resumes.
+    pub async fn on_signal(handles: Vec<ServerHandle>, timeout: u64) {
+        let signal = signal().await;
+        tracing::info!("Received {signal}, draining in-flight requests (timeout {timeout}s)");
+        for handle in handles {
+            handle.stop(true).await;
+        }
+    }
+}
+
+mod graphql {
+    use super::*;
+    use async_graphql::http::{playground_source, GraphQLPlaygroundConfig};
+    use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
+    use phosphor_server::graphql::PhosphorSchema;
+
+    #[post("/graphql")]
+    #[tracing::instrument(level = "info", skip_all)]
+    pub async fn execute(
+        schema: web::Data<PhosphorSchema>,
+        request: GraphQLRequest,
+    ) -> GraphQLResponse {
+        schema.execute(request.into_inner()).await.into()
+    }
+
+    #[get("/graphql/playground")]
+    pub async fn playground() -> HttpResponse {
+        HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(playground_source(GraphQLPlaygroundConfig::new("/graphql")))
+    }
+}
+
+mod request_id {
+    use actix_web::{
+        body::MessageBody,
+        dev::{ServiceRequest, ServiceResponse},
+        http::header::{HeaderName, HeaderValue},
+        middleware::Next,
+    };
+    use phosphor_server::error::REQUEST_ID;
+    use tracing::Instrument;
+
+    const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+    /// Longest client-supplied request id that is accepted; longer ids are replaced.
+    const MAX_LEN: usize = 128;
+
+    /// Tags each request with an `X-Request-Id`, taken from the request if the client sent a
+    /// reasonable one and generated otherwise. The id is recorded on a tracing span wrapping the
+    /// request, included in error bodies, and echoed in the response header.
+    pub async fn propagate(
+        req: ServiceRequest,
+        next: Next<impl MessageBody>,
+    ) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+        let id = req
+            .headers()
+            .get(&X_REQUEST_ID)
+            .and_then(|h| h.to_str().ok())
+            .filter(|id| !id.is_empty() && id.len() <= MAX_LEN)
+            .map(str::to_owned)
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        let span = tracing::info_span!(
+            "request",
+            request_id = %id,
+            method = %req.method(),
+            path = %req.path(),
+            duration_ms = tracing::field::Empty,
+        );
+        let mut res = REQUEST_ID
+            .scope(id.clone(), next.call(req))
+            .instrument(span)
+            .await?;
+
+        if let Ok(value) = HeaderValue::from_str(&id) {
+            res.headers_mut().insert(X_REQUEST_ID, value);
+        }
+        Ok(res)
+    }
+}
+
+mod metrics {
+    use super::*;
+    use actix_web::{
+        body::MessageBody,
+        dev::{ServiceRequest, ServiceResponse},
+        middleware::Next,
+    };
+    use std::time::Instant;
+
+    /// How often the database pool's usage is published as metrics.
+    pub const POOL_SAMPLE_INTERVAL: Duration = Duration::from_secs(15);
+
+    /// Records the count, status, and duration of each request, labelled by the matched route.
+    pub async fn track_requests(
+        req: ServiceRequest,
+        next: Next<impl MessageBody>,
+    ) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+        let start = Instant::now();
+        let method = req.method().to_string();
+        let res = next.call(req).await?;
+        let route = res
+            .request()
+            .match_pattern()
+            .unwrap_or_else(|| "unmatched".to_string());
+        phosphor_server::metrics::observe_request(
+            &method,
+            &route,
+            res.status().as_u16(),
+            start.elapsed(),
+        );
+        Ok(res)
+    }
+
+    #[get("/metrics")]
+    pub async fn export() -> Result<HttpResponse, ApiError> {
+        let body = phosphor_server::metrics::render()
+            .map_err(|e| ApiError::Internal(format!("Failed to encode metrics: {e}")))?;
+        Ok(HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4")
+            .body(body))
+    }
+}
+
+mod health {
+    use super::*;
+    use utoipa::ToSchema;
+
+    #[derive(Serialize, ToSchema)]
+    #[serde(rename_all = "snake_case")]
+    enum HealthStatus {
+        Healthy,
+        Degraded,
+        Down,
+    }
+
+    #[derive(Serialize, ToSchema)]
+    struct HealthResponse {
+        status: HealthStatus,
+        /// Whether the database has been migrated.
+        tables_present: bool,
+        /// The number of icons in the database, or `null` if it is unreachable or unmigrated.
+        icon_count: Option<u64>,
+    }
+
+    impl HealthResponse {
+        const DOWN: Self = HealthResponse {
+            status: HealthStatus::Down,
+            tables_present: false,
+            icon_count: None,
+        };
+    }
+
+    #[utoipa::path(
+        description = "Reports the health of the API. Returns `healthy` if the database is reachable, migrated, and has icons loaded, `degraded` if it is reachable but missing tables or empty, and `down` if the database is unreachable.",
+        responses(
+            (
+                status = OK,
+                body = HealthResponse,
+                example = json!(HealthResponse { status: HealthStatus::Healthy, tables_present: true, icon_count: Some(1512) }),,
+                description = "Service is healthy",
+            ),
+            (
+                status = SERVICE_UNAVAILABLE,
+                body = HealthResponse,
+                example = json!(HealthResponse::DOWN),,
+                description = "Service is down, unreachable",
+            ),
+            (
+                status = INTERNAL_SERVER_ERROR,
+                body = HealthResponse,
+                example = json!(HealthResponse { status: HealthStatus::Degraded, tables_present: false, icon_count: None }),,
+                description = "Service is degraded, connected but not migrated or without icons",
+            ),
+        ),
+        tag = "Other endpoints",
+    )]
+    #[get("/health")]
+    #[tracing::instrument(level = "info", skip(data))]
+    async fn health_check(data: web::Data<app::AppState>) -> impl Responder {
+        if let Err(e) = data.db.ping().await {
+            tracing::error!("Database ping failed: {e}");
+            return HttpResponse::ServiceUnavailable().json(HealthResponse::DOWN);
+        }
+
+        let readiness = match data.db.readiness().await {
+            Ok(readiness) => readiness,
+            Err(e) => {
+                tracing::error!("Database readiness check failed: {e}");
+                return HttpResponse::InternalServerError().json(HealthResponse {
+                    status: HealthStatus::Degraded,
+                    tables_present: false,
+                    icon_count: None,
+                });
+            }
+        };
+        let response = HealthResponse {
+            status: if readiness.is_ready() {
+                HealthStatus::Healthy
+            } else {
+                HealthStatus::Degraded
+            },
+            tables_present: readiness.tables_present,
+            icon_count: readiness.icon_count,
+        };
+        if readiness.is_ready() {
+            HttpResponse::Ok().json(response)
+        } else {
+            tracing::warn!("Database is reachable but not ready: {readiness:?}");
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+
+    #[get("/dump")]
+    #[tracing::instrument(level = "info", skip(data))]
+    pub async fn dump(data: web::Data<app::AppState>) -> Result<HttpResponse, ApiError> {
+        let stats = data.db.dump_stats().await?;
+        Ok(HttpResponse::Ok().json(stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        http::{header, Method, StatusCode},
+        test,
+    };
+    use phosphor_server::{
+        config::{AppSheetConfig, PageSize, Secret, SyncConfig},
+        db::Db,
+        synonyms::Synonyms,
+    };
+    use sea_orm::DatabaseConnection;
+
+    const ORIGIN: &str = "https://phosphoricons.com";
+
+    /// Every `/v1` route, with its method.
+    const V1_ROUTES: &[(&str, &str)] = &[
+        ("GET", "/v1/icon/2884"),
+        ("GET", "/v1/icon/2884/weights"),
+        ("GET", "/v1/icon/by-name/cube"),
+        ("GET", "/v1/icon/by-alias/cube"),
+        ("GET", "/v1/icon/2884/bold.svg"),
+        ("GET", "/v1/icon/2884/react"),
+        ("GET", "/v1/icon/2884/bold.png"),
+        ("GET", "/v1/icons"),
+        ("POST", "/v1/icons/batch"),
+        ("GET", "/v1/icons/latest"),
+        ("GET", "/v1/icons/random"),
+        ("GET", "/v1/feed.xml"),
+        ("GET", "/v1/sprite.svg"),
+        ("GET", "/v1/category/design/sprite.svg"),
+        ("GET", "/v1/icons.css"),
+        ("GET", "/v1/download.zip"),
+        ("GET", "/v1/search"),
+        ("GET", "/v1/autocomplete"),
+        ("GET", "/v1/deprecated"),
+        ("GET", "/v1/deprecations"),
+        ("GET", "/v1/codepoints"),
+        ("GET", "/v1/version/2.1/changes"),
+        ("GET", "/v1/diff"),
+        ("GET", "/v1/pipeline"),
+        ("GET", "/v1/upcoming"),
+        ("GET", "/v1/info"),
+        ("GET", "/v1/categories"),
+        ("GET", "/v1/tags"),
+        ("GET", "/v1/filters"),
+    ];
+
+    /// Every admin route, with its method.
+    const ADMIN_ROUTES: &[(&str, &str)] = &[
+        ("PATCH", "/admin/icon/96cR4kqjHO16pBVCiXg_Ep"),
+        ("POST", "/admin/import"),
+        ("POST", "/v1/admin/sync"),
+        ("POST", "/admin/cache/prime"),
+        (
+            "GET",
+            "/admin/cache/prime/6f1c2a52-0c4e-4d3b-9a53-3b1f1d0c2e7a",
+        ),
+    ];
+
+    fn config(cors_allowed_origins: &[&str], rate_limit: u32) -> Config {
+        Config {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            workers: 3,
+            metrics_addr: None,
+            database_url: Secret::new("postgres://localhost/unused"),
+            current_version: None,
+            search_threshold: 0.2,
+            search_min_threshold: 0.0,
+            search_max_distance: 2,
+            synonyms_path: "./synonyms.json".to_string(),
+            cors_allowed_origins: cors_allowed_origins
+                .iter()
+                .map(|origin| origin.to_string())
+                .collect(),
+            icon_max_age: 60,
+            query_max_age: 60,
+            rate_limit,
+            trust_proxy: false,
+            shutdown_timeout: 1,
+            admin_keys: vec![],
+            page_size: PageSize::default(),
+            sync: SyncConfig {
+                table: false,
+                incremental: false,
+                strict: false,
+                assets: false,
+                normalize_color: true,
+                optimize_svg: true,
+                webhook_url: None,
+            },
+            appsheet: AppSheetConfig {
+                region: "www.appsheet.com".to_string(),
+                app_id: "app".to_string(),
+                table_name: "Icon Inventory".to_string(),
+                application_key: None,
+            },
+        }
+    }
+
+    /// The full app around a database that is never connected. Requests reaching the database
+    /// panic, so only routes that fail or return before querying it can be called.
+    fn test_app(
+        config: Config,
+    ) -> App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<impl MessageBody>,
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        let db = Db {
+            conn: DatabaseConnection::Disconnected,
+            current_version: None,
+            search_threshold: config.search_threshold,
+            search_min_threshold: config.search_min_threshold,
+            search_max_distance: config.search_max_distance,
+            page_size: config.page_size,
+        };
+        let data = web::Data::new(app::AppState::new(config, db, Synonyms::default()));
+        let schema = web::Data::new(phosphor_server::graphql::schema(data.clone().into_inner()));
+        build_app(data, schema, false)
+    }
+
+    fn preflight(method: &str, path: &str, origin: &str) -> test::TestRequest {
+        test::TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri(path)
+            .insert_header((header::ORIGIN, origin))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, method))
+            .insert_header((
+                header::ACCESS_CONTROL_REQUEST_HEADERS,
+                "authorization,content-type,x-api-key",
+            ))
+    }
+
+    fn header_value(res: &ServiceResponse<impl MessageBody>, name: header::HeaderName) -> &str {
+        res.headers()
+            .get(&name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_else(|| panic!("{name} is missing from {} response", res.status()))
+    }
+
+    #[actix_web::test]
+    async fn preflights_allow_every_route() {
+        let app = test::init_service(test_app(config(&["*"], 0))).await;
+        for &(method, path) in V1_ROUTES.iter().chain(ADMIN_ROUTES) {
+            let res = test::call_service(&app, preflight(method, path, ORIGIN).to_request()).await;
+            assert_eq!(res.status(), StatusCode::OK, "{method} {path}");
+            assert_eq!(
+                header_value(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+                "*",
+                "{method} {path}"
+            );
+            let methods = header_value(&res, header::ACCESS_CONTROL_ALLOW_METHODS);
+            assert!(methods.contains(method), "{method} {path}: {methods}");
+            let headers = header_value(&res, header::ACCESS_CONTROL_ALLOW_HEADERS).to_lowercase();
+            for requested in ["authorization", "content-type", "x-api-key"] {
+                assert!(headers.contains(requested), "{method} {path}: {headers}");
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn preflights_only_allow_configured_origins() {
+        let app = test::init_service(test_app(config(&[ORIGIN], 0))).await;
+        let res =
+            test::call_service(&app, preflight("GET", "/v1/icons", ORIGIN).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            header_value(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN),
+            ORIGIN
+        );
+
+        let res = test::call_service(
+            &app,
+            preflight("GET", "/v1/icons", "https://example.com").to_request(),
+        )
+        .await;
+        assert!(!res.status().is_success());
+        assert!(!res
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[actix_web::test]
+    async fn error_responses_keep_cors_headers() {
+        let app = test::init_service(test_app(config(&["*"], 0))).await;
+        for (uri, status) in [
+            ("/v1/icon/2884/heavy.svg", StatusCode::BAD_REQUEST),
+            ("/v1/icons?order=size", StatusCode::BAD_REQUEST),
+            ("/v1/nonexistent", StatusCode::NOT_FOUND),
+        ] {
+            let req = test::TestRequest::get()
+                .uri(uri)
+                .insert_header((header::ORIGIN, ORIGIN))
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), status, "{uri}");
+            assert_eq!(header_value(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN), "*");
+        }
+
+        // Admin endpoints are disabled without keys.
+        let req = test::TestRequest::post()
+            .uri("/v1/admin/sync")
+            .insert_header((header::ORIGIN, ORIGIN))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(header_value(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN), "*");
+    }
+
+    #[actix_web::test]
+    async fn overlong_searches_are_rejected() {
+        let app = test::init_service(test_app(config(&["*"], 0))).await;
+        let uri = format!("/v1/search?q={}", "a".repeat(300));
+        let res = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn rate_limited_responses_keep_cors_headers() {
+        let app = test::init_service(test_app(config(&["*"], 1))).await;
+        let peer = "192.0.2.83:40000".parse().unwrap();
+        let request = || {
+            test::TestRequest::get()
+                .uri("/v1/icon/2884/heavy.svg")
+                .peer_addr(peer)
+                .insert_header((header::ORIGIN, ORIGIN))
+                .to_request()
+        };
+        let res = test::call_service(&app, request()).await;
+        assert_ne!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let res = test::call_service(&app, request()).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header_value(&res, header::ACCESS_CONTROL_ALLOW_ORIGIN), "*");
+        assert!(res.headers().contains_key(header::RETRY_AFTER));
+
+        // Preflights are answered before rate limiting.
+        let res = test::call_service(
+            &app,
+            preflight("GET", "/v1/icons", ORIGIN)
+                .peer_addr(peer)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
     }
 }
@@ -0,0 +1,182 @@
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// The registry scraped by `GET /metrics`.
+static REGISTRY: LazyLock<Registry> = LazyLock::new(|| {
+    let registry = Registry::new();
+    registry
+        .register(Box::new(HTTP_REQUESTS.clone()))
+        .and_then(|_| registry.register(Box::new(HTTP_REQUEST_DURATION.clone())))
+        .and_then(|_| registry.register(Box::new(DB_QUERY_DURATION.clone())))
+        .and_then(|_| registry.register(Box::new(ICON_CACHE_SIZE.clone())))
+        .and_then(|_| registry.register(Box::new(CACHE_LOOKUPS.clone())))
+        .and_then(|_| registry.register(Box::new(CACHE_EVICTIONS.clone())))
+        .and_then(|_| registry.register(Box::new(DB_POOL_CONNECTIONS.clone())))
+        .and_then(|_| registry.register(Box::new(DB_POOL_ACQUIRE_FAILURES.clone())))
+        .expect("metrics are registered once with unique names");
+    registry
+});
+
+static HTTP_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "http_requests_total",
+            "HTTP requests handled, by route and status",
+        ),
+        &["method", "route", "status"],
+    )
+    .unwrap()
+});
+
+static HTTP_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    HistogramVec::new(
+        HistogramOpts::new(
+            "http_request_duration_seconds",
+            "Time spent handling HTTP requests, by route",
+        ),
+        &["method", "route"],
+    )
+    .unwrap()
+});
+
+static DB_QUERY_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    HistogramVec::new(
+        HistogramOpts::new(
+            "db_query_duration_seconds",
+            "Time spent executing database queries, by statement kind",
+        )
+        .buckets(vec![
+            0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0,
+        ]),
+        &["operation", "failed"],
+    )
+    .unwrap()
+});
+
+static ICON_CACHE_SIZE: LazyLock<IntGauge> = LazyLock::new(|| {
+    IntGauge::new("icon_cache_size", "Icons held in the in-memory icon cache").unwrap()
+});
+
+static CACHE_LOOKUPS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "cache_lookups_total",
+            "Reads of the in-memory caches, by cache and whether they hit",
+        ),
+        &["cache", "result"],
+    )
+    .unwrap()
+});
+
+static CACHE_EVICTIONS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    IntCounterVec::new(
+        Opts::new(
+            "cache_evictions_total",
+            "Entries dropped from the in-memory caches when the catalog changes, by cache",
+        ),
+        &["cache"],
+    )
+    .unwrap()
+});
+
+static DB_POOL_CONNECTIONS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    IntGaugeVec::new(
+        Opts::new(
+            "db_pool_connections",
+            "Open database connections, by whether they are idle or in use",
+        ),
+        &["state"],
+    )
+    .unwrap()
+});
+
+static DB_POOL_ACQUIRE_FAILURES: LazyLock<IntCounter> = LazyLock::new(|| {
+    IntCounter::new(
+        "db_pool_acquire_failures_total",
+        "Requests that failed because no database connection could be acquired in time",
+    )
+    .unwrap()
+});
+
+/// Record a handled request. `route` should be the matched route pattern rather than the
+/// request path, to keep label cardinality bounded.
+pub fn observe_request(method: &str, route: &str, status: u16, elapsed: Duration) {
+    HTTP_REQUESTS
+        .with_label_values(&[method, route, &status.to_string()])
+        .inc();
+    HTTP_REQUEST_DURATION
+        .with_label_values(&[method, route])
+        .observe(elapsed.as_secs_f64());
+}
+
+/// Record a database query, labelled by the leading SQL keyword (`select`, `insert`, ...).
+pub fn observe_query(sql: &str, elapsed: Duration, failed: bool) {
+    let operation = sql
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    DB_QUERY_DURATION
+        .with_label_values(&[operation.as_str(), if failed { "true" } else { "false" }])
+        .observe(elapsed.as_secs_f64());
+}
+
+pub fn set_icon_cache_size(size: usize) {
+    ICON_CACHE_SIZE.set(size as i64);
+}
+
+pub fn observe_cache(cache: &str, hit: bool) {
+    CACHE_LOOKUPS
+        .with_label_values(&[cache, if hit { "hit" } else { "miss" }])
+        .inc();
+}
+
+pub fn record_cache_evictions(cache: &str, count: usize) {
+    CACHE_EVICTIONS
+        .with_label_values(&[cache])
+        .inc_by(count as u64);
+}
+
+/// Record the state of the connection pool: `size` connections are open, of which `idle` are
+/// waiting to be used.
+pub fn set_db_pool(size: u32, idle: usize) {
+    let idle = idle as i64;
+    DB_POOL_CONNECTIONS.with_label_values(&["idle"]).set(idle);
+    DB_POOL_CONNECTIONS
+        .with_label_values(&["in_use"])
+        .set(i64::from(size) - idle);
+}
+
+pub fn record_pool_acquire_failure() {
+    DB_POOL_ACQUIRE_FAILURES.inc();
+}
+
+/// Render every metric in the Prometheus text exposition format.
+pub fn render() -> Result<String, prometheus::Error> {
+    let mut buf = Vec::new();
+    TextEncoder::new().encode(&REGISTRY.gather(), &mut buf)?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exports_cache_and_pool_metrics() {
+        observe_cache("test", true);
+        observe_cache("test", false);
+        observe_cache("test", false);
+        set_db_pool(10, 4);
+
+        let body = render().unwrap();
+        assert!(body.contains(r#"cache_lookups_total{cache="test",result="hit"} 1"#));
+        assert!(body.contains(r#"cache_lookups_total{cache="test",result="miss"} 2"#));
+        assert!(body.contains(r#"db_pool_connections{state="idle"} 4"#));
+        assert!(body.contains(r#"db_pool_connections{state="in_use"} 6"#));
+    }
+}
@@ -0,0 +1,150 @@
+//! Token buckets for per-client rate limiting, held in the app state and consulted by the rate
+//! limiting middleware on every request.
+
+use dashmap::DashMap;
+use std::{
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+/// The most clients tracked at once. Past this, the buckets that have refilled completely are
+/// dropped, and if that isn't enough, the least recently used ones are too.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// The tokens the bucket will hold at `now`, once refilled.
+    fn tokens_at(&self, now: Instant, capacity: f64) -> f64 {
+        let rate = capacity / 60.0;
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        (self.tokens + elapsed * rate).min(capacity)
+    }
+
+    fn refill(&mut self, now: Instant, capacity: f64) {
+        self.tokens = self.tokens_at(now, capacity);
+        self.updated = now;
+    }
+
+    /// Takes a token, or returns how long until one is available.
+    fn take(&mut self, capacity: f64) -> Result<(), Duration> {
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            let rate = capacity / 60.0;
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / rate))
+        }
+    }
+}
+
+pub struct RateLimiter {
+    buckets: DashMap<IpAddr, Bucket>,
+    max_clients: usize,
+}
+
+/// Summarizes the buckets rather than printing every client.
+impl std::fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("clients", &self.buckets.len())
+            .finish()
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        RateLimiter {
+            buckets: DashMap::new(),
+            max_clients: MAX_TRACKED_CLIENTS,
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Takes a token from `ip`'s bucket, which holds `rpm` tokens and refills over a minute, or
+    /// returns how long until one is available.
+    pub fn take(&self, ip: IpAddr, rpm: u32, now: Instant) -> Result<(), Duration> {
+        let capacity = f64::from(rpm);
+        if self.buckets.len() >= self.max_clients && !self.buckets.contains_key(&ip) {
+            self.evict(now, capacity);
+        }
+        self.buckets
+            .entry(ip)
+            .and_modify(|bucket| bucket.refill(now, capacity))
+            .or_insert(Bucket {
+                tokens: capacity,
+                updated: now,
+            })
+            .take(capacity)
+    }
+
+    /// Drop the buckets that have refilled completely, which are no different from new ones, and
+    /// then the least recently used ones until a tenth of the limit is free, so that the buckets
+    /// aren't scanned again until that many new clients have arrived.
+    fn evict(&self, now: Instant, capacity: f64) {
+        self.buckets
+            .retain(|_, bucket| bucket.tokens_at(now, capacity) < capacity);
+
+        let target = self.max_clients - (self.max_clients / 10).max(1).min(self.max_clients);
+        let excess = self.buckets.len().saturating_sub(target);
+        if excess == 0 {
+            return;
+        }
+        let mut ages = self
+            .buckets
+            .iter()
+            .map(|bucket| (bucket.updated, *bucket.key()))
+            .collect::<Vec<_>>();
+        ages.select_nth_unstable(excess - 1);
+        for (_, ip) in &ages[..excess] {
+            self.buckets.remove(ip);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(max_clients: usize) -> RateLimiter {
+        RateLimiter {
+            buckets: DashMap::new(),
+            max_clients,
+        }
+    }
+
+    fn ip(n: u32) -> IpAddr {
+        IpAddr::from(n.to_be_bytes())
+    }
+
+    #[test]
+    fn clients_are_limited_independently() {
+        let limiter = limiter(10);
+        let now = Instant::now();
+        assert!(limiter.take(ip(1), 2, now).is_ok());
+        assert!(limiter.take(ip(1), 2, now).is_ok());
+        let wait = limiter.take(ip(1), 2, now).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(30));
+        assert!(limiter.take(ip(2), 2, now).is_ok());
+        assert!(limiter.take(ip(1), 2, now + wait).is_ok());
+    }
+
+    #[test]
+    fn tracked_clients_are_capped() {
+        let limiter = limiter(100);
+        let start = Instant::now();
+        // Every client is mid-refill, so none can be dropped for having refilled.
+        for n in 0..1_000 {
+            let now = start + Duration::from_millis(u64::from(n));
+            assert!(limiter.take(ip(n), 6, now).is_ok());
+            assert!(limiter.buckets.len() <= 100);
+        }
+        // The most recent clients are kept, and the earliest were evicted.
+        assert!(limiter.buckets.contains_key(&ip(999)));
+        assert!(!limiter.buckets.contains_key(&ip(0)));
+    }
+}
@@ -0,0 +1,270 @@
+//! Rendered icon assets, kept in memory so that the raw SVG and PNG endpoints can skip the
+//! database and the rasterizer for icons that were already served or primed.
+//!
+//! The cache is cleared whenever the catalog or its assets are synced, and can be filled ahead of
+//! demand with `POST /admin/cache/prime`, which runs as a background [`PrimeJob`].
+
+use crate::{icons::IconWeight, metrics};
+use actix_web::web::Bytes;
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Once the cache holds this many renders, new ones are served without being stored. This is
+/// several times the size of the full catalog in every weight and a few PNG sizes.
+const MAX_ENTRIES: usize = 50_000;
+/// Once the cached renders add up to this many bytes, new ones are served without being stored,
+/// so that clients requesting many PNG sizes can't exhaust memory.
+const MAX_BYTES: usize = 256 * 1024 * 1024;
+
+pub struct RenderCache {
+    svgs: DashMap<(i32, IconWeight), Arc<str>>,
+    pngs: DashMap<(i32, IconWeight, u32), Bytes>,
+    /// The total size of the cached renders.
+    bytes: AtomicUsize,
+    max_bytes: usize,
+}
+
+/// Summarizes the cache rather than printing every render.
+impl std::fmt::Debug for RenderCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RenderCache")
+            .field("svgs", &self.svgs.len())
+            .field("pngs", &self.pngs.len())
+            .field("bytes", &self.bytes())
+            .finish()
+    }
+}
+
+impl Default for RenderCache {
+    fn default() -> Self {
+        RenderCache {
+            svgs: DashMap::new(),
+            pngs: DashMap::new(),
+            bytes: AtomicUsize::new(0),
+            max_bytes: MAX_BYTES,
+        }
+    }
+}
+
+impl RenderCache {
+    pub fn svg(&self, icon_id: i32, weight: &IconWeight) -> Option<Arc<str>> {
+        let src = self
+            .svgs
+            .get(&(icon_id, weight.clone()))
+            .map(|src| src.clone());
+        metrics::observe_cache("svgs", src.is_some());
+        src
+    }
+
+    pub fn insert_svg(&self, icon_id: i32, weight: &IconWeight, src: Arc<str>) {
+        if self.reserve(src.len()) {
+            let replaced = self.svgs.insert((icon_id, weight.clone()), src);
+            self.release(replaced.map_or(0, |src| src.len()));
+        }
+    }
+
+    pub fn png(&self, icon_id: i32, weight: &IconWeight, size: u32) -> Option<Bytes> {
+        let png = self
+            .pngs
+            .get(&(icon_id, weight.clone(), size))
+            .map(|png| png.clone());
+        metrics::observe_cache("pngs", png.is_some());
+        png
+    }
+
+    pub fn insert_png(&self, icon_id: i32, weight: &IconWeight, size: u32, png: Bytes) {
+        if self.reserve(png.len()) {
+            let replaced = self.pngs.insert((icon_id, weight.clone(), size), png);
+            self.release(replaced.map_or(0, |png| png.len()));
+        }
+    }
+
+    /// Count `size` more bytes towards the budget, unless the cache is full.
+    fn reserve(&self, size: usize) -> bool {
+        if self.len() >= MAX_ENTRIES {
+            return false;
+        }
+        self.bytes
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |bytes| {
+                bytes
+                    .checked_add(size)
+                    .filter(|&bytes| bytes <= self.max_bytes)
+            })
+            .is_ok()
+    }
+
+    fn release(&self, size: usize) {
+        self.bytes.fetch_sub(size, Ordering::AcqRel);
+    }
+
+    /// The total size of the renders held, in bytes.
+    pub fn bytes(&self) -> usize {
+        self.bytes.load(Ordering::Acquire)
+    }
+
+    /// The number of renders held, across every format.
+    pub fn len(&self) -> usize {
+        self.svgs.len() + self.pngs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        metrics::record_cache_evictions("svgs", self.svgs.len());
+        metrics::record_cache_evictions("pngs", self.pngs.len());
+        self.svgs.clear();
+        self.pngs.clear();
+        self.bytes.store(0, Ordering::Release);
+    }
+}
+
+/// A background job rendering every published icon into the [`RenderCache`], started by
+/// [`crate::app::AppState::prime_renders`]. Progress is updated as each render completes.
+#[derive(Debug)]
+pub struct PrimeJob {
+    pub id: Uuid,
+    total: AtomicUsize,
+    rendered: AtomicUsize,
+    failed: AtomicUsize,
+    done: AtomicBool,
+}
+
+impl PrimeJob {
+    pub fn new() -> Self {
+        PrimeJob {
+            id: Uuid::new_v4(),
+            total: AtomicUsize::new(0),
+            rendered: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+            done: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set_total(&self, total: usize) {
+        self.total.store(total, Ordering::Release);
+    }
+
+    pub(crate) fn record(&self, ok: bool) {
+        let counter = if ok { &self.rendered } else { &self.failed };
+        counter.fetch_add(1, Ordering::AcqRel);
+    }
+
+    pub(crate) fn finish(&self) {
+        self.done.store(true, Ordering::Release);
+    }
+
+    pub fn status(&self) -> PrimeStatus {
+        PrimeStatus {
+            id: self.id,
+            state: if self.is_done() {
+                JobState::Completed
+            } else {
+                JobState::Running
+            },
+            total: self.total.load(Ordering::Acquire),
+            rendered: self.rendered.load(Ordering::Acquire),
+            failed: self.failed.load(Ordering::Acquire),
+        }
+    }
+}
+
+impl Default for PrimeJob {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Completed,
+}
+
+/// The progress of a cache-priming job.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct PrimeStatus {
+    pub id: Uuid,
+    pub state: JobState,
+    /// The number of renders the job will produce: one per published icon, stored weight, and
+    /// requested format. `0` until the icons have been loaded.
+    pub total: usize,
+    /// Renders stored in the cache so far.
+    pub rendered: usize,
+    /// Renders that failed, such as SVGs the rasterizer rejected.
+    pub failed: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caches_renders_until_cleared() {
+        let cache = RenderCache::default();
+        cache.insert_svg(1, &IconWeight::Bold, Arc::from("<svg/>"));
+        cache.insert_png(1, &IconWeight::Bold, 64, Bytes::from_static(b"png"));
+
+        assert_eq!(cache.svg(1, &IconWeight::Bold).as_deref(), Some("<svg/>"));
+        assert!(cache.svg(1, &IconWeight::Fill).is_none());
+        assert_eq!(
+            cache.png(1, &IconWeight::Bold, 64),
+            Some(Bytes::from_static(b"png"))
+        );
+        assert!(cache.png(1, &IconWeight::Bold, 128).is_none());
+        assert_eq!(cache.len(), 2);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn stops_caching_past_the_byte_budget() {
+        let cache = RenderCache {
+            max_bytes: 10,
+            ..RenderCache::default()
+        };
+        cache.insert_png(1, &IconWeight::Bold, 64, Bytes::from_static(b"123456"));
+        // Would take the cache to 12 bytes.
+        cache.insert_png(1, &IconWeight::Bold, 128, Bytes::from_static(b"123456"));
+        assert!(cache.png(1, &IconWeight::Bold, 128).is_none());
+        cache.insert_svg(1, &IconWeight::Bold, Arc::from("<svg/>"));
+        assert!(cache.svg(1, &IconWeight::Bold).is_none());
+        cache.insert_svg(1, &IconWeight::Bold, Arc::from("<g/>"));
+        assert_eq!(cache.bytes(), 10);
+
+        cache.clear();
+        assert_eq!(cache.bytes(), 0);
+
+        // Replacing a render frees the old one's bytes.
+        cache.insert_png(1, &IconWeight::Bold, 64, Bytes::from_static(b"123"));
+        cache.insert_png(1, &IconWeight::Bold, 64, Bytes::from_static(b"12"));
+        assert_eq!(cache.bytes(), 2);
+    }
+
+    #[test]
+    fn reports_job_progress() {
+        let job = PrimeJob::new();
+        job.set_total(3);
+        job.record(true);
+        job.record(false);
+        let status = job.status();
+        assert_eq!(status.state, JobState::Running);
+        assert_eq!((status.total, status.rendered, status.failed), (3, 1, 1));
+
+        job.finish();
+        assert_eq!(job.status().state, JobState::Completed);
+    }
+}
@@ -1,7 +1,10 @@
 use crate::entities::svgs::Model;
 use crate::icons::IconWeight;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
+use std::sync::LazyLock;
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Svg {
@@ -32,3 +35,522 @@ impl From<Svg> for Model {
         }
     }
 }
+
+static HARDCODED_BLACK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)\b(fill|stroke)(="|:\s*)(#000000|#000|black)\b"#).unwrap());
+
+/// Replace hardcoded black fills and strokes, in both attributes and inline styles, with
+/// `currentColor` so that the icon can be themed. Returns `None` if the source needed no changes.
+pub fn normalize_color(src: &str) -> Option<String> {
+    if !HARDCODED_BLACK.is_match(src) {
+        return None;
+    }
+    Some(
+        HARDCODED_BLACK
+            .replace_all(src, "${1}${2}currentColor")
+            .into_owned(),
+    )
+}
+
+static XML_PROLOG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<\?xml.*?\?>|<!DOCTYPE[^>]*>").unwrap());
+static COMMENT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<!--.*?-->").unwrap());
+static EDITOR_ELEMENT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"(?s)<metadata\b[^>]*/>|<metadata\b.*?</metadata>",
+        r"|<(?:sodipodi|inkscape):[\w-]+\b[^>]*/>",
+        r"|<(?:sodipodi|inkscape):[\w-]+\b.*?</(?:sodipodi|inkscape):[\w-]+>",
+    ))
+    .unwrap()
+});
+static EDITOR_ATTRIBUTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\s+(xmlns:)?(sodipodi|inkscape)(:[\w-]+)?="[^"]*""#).unwrap());
+static WHITESPACE_BETWEEN_TAGS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r">\s+<").unwrap());
+static WHITESPACE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+").unwrap());
+
+/// Strip everything an SVG export tool leaves behind that doesn't affect rendering: the XML
+/// prolog and doctype, comments, editor metadata, and insignificant whitespace. Optimizing an
+/// already-optimized SVG returns it unchanged.
+pub fn optimize(src: &str) -> String {
+    let src = XML_PROLOG.replace_all(src, "");
+    let src = COMMENT.replace_all(&src, "");
+    let src = EDITOR_ELEMENT.replace_all(&src, "");
+    let src = EDITOR_ATTRIBUTE.replace_all(&src, "");
+    let src = WHITESPACE_BETWEEN_TAGS.replace_all(&src, "><");
+    let src = WHITESPACE.replace_all(&src, " ");
+    src.trim().to_string()
+}
+
+static SVG_ROOT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^<svg\b[^>]*>").unwrap());
+static VIEW_BOX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\sviewBox\s*=\s*("[^"]+"|'[^']+')"#).unwrap());
+
+/// Check that `src` is plausibly a complete icon: a single root `<svg>` element carrying a
+/// `viewBox`, with nothing but the XML prolog, comments, and whitespace around it. This is a
+/// cheap structural check meant to catch truncated or mislabelled files, not a full XML parse.
+pub fn validate(src: &str) -> Result<(), String> {
+    let src = XML_PROLOG.replace_all(src, "");
+    let src = COMMENT.replace_all(&src, "");
+    let src = src.trim();
+
+    let root = SVG_ROOT
+        .find(src)
+        .ok_or_else(|| "Missing root <svg> element".to_string())?;
+    if !VIEW_BOX.is_match(root.as_str()) {
+        return Err("Root <svg> element has no viewBox".to_string());
+    }
+    if !src.ends_with("</svg>") && !root.as_str().ends_with("/>") {
+        return Err("Root <svg> element is not closed".to_string());
+    }
+    let opened = src.matches("<svg").count();
+    let closed = src.matches("</svg>").count() + usize::from(root.as_str().ends_with("/>"));
+    if opened != closed {
+        return Err("Unbalanced <svg> elements".to_string());
+    }
+    Ok(())
+}
+
+/// Split an asset file name like `cube-bold.svg` into the icon name and weight. Weight suffixes
+/// are only recognized at the very end of the stem, and files without one are regular weight,
+/// so names that merely contain a weight (`bold-arrow.svg`) are left intact.
+pub fn parse_icon_name_and_weight(file_name: &str) -> Option<(String, IconWeight)> {
+    let stem = file_name.strip_suffix(".svg")?;
+    let (name, weight) = IconWeight::ALL
+        .into_iter()
+        .filter(|weight| *weight != IconWeight::Regular)
+        .find_map(|weight| {
+            stem.strip_suffix(&format!("-{weight}"))
+                .map(|name| (name, weight))
+        })
+        .unwrap_or((stem, IconWeight::Regular));
+    (!name.is_empty()).then(|| (name.to_string(), weight))
+}
+
+/// The icon name of an asset file found in the directory for `weight`. The directory is
+/// authoritative: its weight's suffix is stripped if present, and regular weight files keep their
+/// whole stem. A suffix naming a different weight is logged, since the file was likely misfiled.
+pub fn parse_icon_name_for_weight(file_name: &str, weight: &IconWeight) -> Option<String> {
+    let (parsed_name, parsed_weight) = parse_icon_name_and_weight(file_name)?;
+    if parsed_weight == *weight {
+        return Some(parsed_name);
+    }
+    tracing::warn!(
+        "Asset {file_name} looks like a {parsed_weight} icon, but is filed under {weight}"
+    );
+    let stem = file_name.strip_suffix(".svg")?;
+    let name = match weight {
+        IconWeight::Regular => stem,
+        _ => stem.strip_suffix(&format!("-{weight}")).unwrap_or(stem),
+    };
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Rasterize `src` to a square PNG `size` pixels wide, scaling the icon's viewBox to fit.
+pub fn render_png(src: &str, size: u32) -> Result<Vec<u8>, String> {
+    let tree = resvg::usvg::Tree::from_str(src, &resvg::usvg::Options::default())
+        .map_err(|e| format!("Failed to parse SVG: {e}"))?;
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size, size)
+        .ok_or_else(|| format!("Invalid image size: {size}"))?;
+
+    let tree_size = tree.size();
+    let scale = size as f32 / tree_size.width().max(tree_size.height());
+    resvg::render(
+        &tree,
+        resvg::tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    pixmap
+        .encode_png()
+        .map_err(|e| format!("Failed to encode PNG: {e}"))
+}
+
+/// The CSS named colors, plus `transparent`.
+const NAMED_COLORS: [&str; 149] = [
+    "aliceblue",
+    "antiquewhite",
+    "aqua",
+    "aquamarine",
+    "azure",
+    "beige",
+    "bisque",
+    "black",
+    "blanchedalmond",
+    "blue",
+    "blueviolet",
+    "brown",
+    "burlywood",
+    "cadetblue",
+    "chartreuse",
+    "chocolate",
+    "coral",
+    "cornflowerblue",
+    "cornsilk",
+    "crimson",
+    "cyan",
+    "darkblue",
+    "darkcyan",
+    "darkgoldenrod",
+    "darkgray",
+    "darkgreen",
+    "darkgrey",
+    "darkkhaki",
+    "darkmagenta",
+    "darkolivegreen",
+    "darkorange",
+    "darkorchid",
+    "darkred",
+    "darksalmon",
+    "darkseagreen",
+    "darkslateblue",
+    "darkslategray",
+    "darkslategrey",
+    "darkturquoise",
+    "darkviolet",
+    "deeppink",
+    "deepskyblue",
+    "dimgray",
+    "dimgrey",
+    "dodgerblue",
+    "firebrick",
+    "floralwhite",
+    "forestgreen",
+    "fuchsia",
+    "gainsboro",
+    "ghostwhite",
+    "gold",
+    "goldenrod",
+    "gray",
+    "green",
+    "greenyellow",
+    "grey",
+    "honeydew",
+    "hotpink",
+    "indianred",
+    "indigo",
+    "ivory",
+    "khaki",
+    "lavender",
+    "lavenderblush",
+    "lawngreen",
+    "lemonchiffon",
+    "lightblue",
+    "lightcoral",
+    "lightcyan",
+    "lightgoldenrodyellow",
+    "lightgray",
+    "lightgreen",
+    "lightgrey",
+    "lightpink",
+    "lightsalmon",
+    "lightseagreen",
+    "lightskyblue",
+    "lightslategray",
+    "lightslategrey",
+    "lightsteelblue",
+    "lightyellow",
+    "lime",
+    "limegreen",
+    "linen",
+    "magenta",
+    "maroon",
+    "mediumaquamarine",
+    "mediumblue",
+    "mediumorchid",
+    "mediumpurple",
+    "mediumseagreen",
+    "mediumslateblue",
+    "mediumspringgreen",
+    "mediumturquoise",
+    "mediumvioletred",
+    "midnightblue",
+    "mintcream",
+    "mistyrose",
+    "moccasin",
+    "navajowhite",
+    "navy",
+    "oldlace",
+    "olive",
+    "olivedrab",
+    "orange",
+    "orangered",
+    "orchid",
+    "palegoldenrod",
+    "palegreen",
+    "paleturquoise",
+    "palevioletred",
+    "papayawhip",
+    "peachpuff",
+    "peru",
+    "pink",
+    "plum",
+    "powderblue",
+    "purple",
+    "rebeccapurple",
+    "red",
+    "rosybrown",
+    "royalblue",
+    "saddlebrown",
+    "salmon",
+    "sandybrown",
+    "seagreen",
+    "seashell",
+    "sienna",
+    "silver",
+    "skyblue",
+    "slateblue",
+    "slategray",
+    "slategrey",
+    "snow",
+    "springgreen",
+    "steelblue",
+    "tan",
+    "teal",
+    "thistle",
+    "tomato",
+    "turquoise",
+    "violet",
+    "wheat",
+    "white",
+    "whitesmoke",
+    "yellow",
+    "yellowgreen",
+    "transparent",
+];
+
+static HEX_COLOR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^#([0-9a-fA-F]{3,4}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})$").unwrap());
+
+/// Replace `currentColor` with a fixed CSS color, which must be a hex color or a CSS named color.
+pub fn recolor(src: &str, color: &str) -> Result<String, String> {
+    let valid = HEX_COLOR.is_match(color) || NAMED_COLORS.contains(&color.to_lowercase().as_str());
+    if !valid {
+        return Err(format!("Invalid color: {color}"));
+    }
+    Ok(src.replace("currentColor", color))
+}
+
+/// A neutral question-mark box served in place of a missing icon, drawn in `currentColor` like
+/// the icons themselves. The requested icon and weight are noted in a comment for debugging.
+pub fn placeholder(icon_id: i32, weight: &IconWeight) -> String {
+    format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 256 256" fill="currentColor">"#,
+            "<!-- Icon not found: {} - {} -->",
+            r#"<rect x="40" y="40" width="176" height="176" rx="8" fill="none" stroke="currentColor" stroke-width="16"/>"#,
+            r#"<path d="M128,76c-22,0-40,16-40,36h16c0-11,11-20,24-20s24,9,24,20c0,9-6,14-14,18-10,5-18,12-18,26v6h16v-6c0-6,3-9,10-12,12-6,22-15,22-32C168,92,150,76,128,76Z"/>"#,
+            r#"<circle cx="128" cy="184" r="12"/>"#,
+            "</svg>"
+        ),
+        icon_id, weight
+    )
+}
+
+static SVG_ELEMENT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<svg\b([^>]*)>(.*)</svg>").unwrap());
+
+/// Wrap the contents of an icon's `<svg>` in a `<symbol>` with the given `id`, for inclusion in a
+/// sprite sheet. Returns `None` if the source has no `<svg>` root with a `viewBox`.
+pub fn to_symbol(src: &str, id: &str) -> Option<String> {
+    let captures = SVG_ELEMENT.captures(src)?;
+    let view_box = VIEW_BOX.captures(&captures[1])?;
+    Some(format!(
+        r#"<symbol id="{id}" viewBox={}>{}</symbol>"#,
+        &view_box[1], &captures[2]
+    ))
+}
+
+/// Encode `svg` as a base64 `data:` URI. Base64 sidesteps the escaping that `#`, quotes, and
+/// angle brackets would otherwise need in URLs and CSS.
+pub fn to_data_uri(svg: &str) -> String {
+    format!("data:image/svg+xml;base64,{}", STANDARD.encode(svg))
+}
+
+/// A CSS rule setting the icon as the background image of elements with the given class, with the
+/// SVG inlined as a data URI.
+pub fn to_css_rule(src: &str, class: &str) -> String {
+    format!(
+        ".{class} {{ background-image: url(\"{}\") }}\n",
+        to_data_uri(src)
+    )
+}
+
+static ATTRIBUTE_NAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\s)([A-Za-z]+(?:[-:][A-Za-z]+)+|class)=").unwrap());
+
+/// The JSX spelling of an SVG attribute: `class` becomes `className`, and hyphenated or namespaced
+/// names like `stroke-width` and `xlink:href` are camelCased. `data-` and `aria-` attributes are
+/// left as they are, as React expects.
+fn jsx_attribute(name: &str) -> String {
+    if name == "class" {
+        return "className".to_string();
+    }
+    if name.starts_with("data-") || name.starts_with("aria-") {
+        return name.to_string();
+    }
+    let mut parts = name.split(['-', ':']);
+    let mut jsx = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            jsx.extend(first.to_uppercase());
+            jsx.push_str(chars.as_str());
+        }
+    }
+    jsx
+}
+
+/// The PascalCase component name for a kebab-case icon name, e.g. `CubeFocus` for `cube-focus`.
+pub fn component_name(name: &str) -> String {
+    let pascal = name
+        .split('-')
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect::<String>();
+    // Identifiers can't start with a digit.
+    if pascal.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("Icon{pascal}")
+    } else {
+        pascal
+    }
+}
+
+/// Wrap an icon's SVG in a React function component named `component`, spreading its props onto
+/// the `<svg>` so that they override the defaults. Icons are drawn in `currentColor`, so they
+/// inherit the text color unless a `fill` prop is given. Returns `None` if the source has no
+/// `<svg>` root.
+pub fn to_react_component(src: &str, component: &str) -> Option<String> {
+    let captures = SVG_ELEMENT.captures(src)?;
+    let jsx = |markup: &str| {
+        ATTRIBUTE_NAME
+            .replace_all(markup, |caps: &regex::Captures| {
+                format!("{}{}=", &caps[1], jsx_attribute(&caps[2]))
+            })
+            .into_owned()
+    };
+    let mut attributes = jsx(&captures[1]);
+    if !attributes.contains(" fill=") {
+        attributes.push_str(r#" fill="currentColor""#);
+    }
+    Some(format!(
+        "export const {component} = (props) => (\n  <svg{attributes} {{...props}}>{}</svg>\n);\n",
+        jsx(&captures[2])
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSY: &str = r##"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd">
+<!-- Created with Inkscape (http://www.inkscape.org/) -->
+<svg
+   xmlns="http://www.w3.org/2000/svg"
+   xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape"
+   xmlns:sodipodi="http://sodipodi.sourceforge.net/DTD/sodipodi-0.dtd"
+   viewBox="0 0 256 256"
+   inkscape:version="1.3"
+   sodipodi:docname="cube.svg">
+  <metadata>
+    <rdf:RDF><cc:Work rdf:about=""/></rdf:RDF>
+  </metadata>
+  <sodipodi:namedview id="base" pagecolor="#ffffff"/>
+  <!-- the cube -->
+  <rect   width="256"
+          height="256" fill="none"/>
+  <path d="M128,24 L224,80 L224,176 L128,232 L32,176 L32,80 Z"
+        fill="currentColor"/>
+</svg>
+"##;
+
+    #[test]
+    fn optimize_strips_editor_output() {
+        let optimized = optimize(MESSY);
+        assert_eq!(
+            optimized,
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 256 256">"#,
+                r#"<rect width="256" height="256" fill="none"/>"#,
+                r#"<path d="M128,24 L224,80 L224,176 L128,232 L32,176 L32,80 Z" fill="currentColor"/>"#,
+                "</svg>"
+            )
+        );
+        assert_eq!(validate(&optimized), Ok(()));
+        assert!(render_png(&optimized, 32).is_ok());
+    }
+
+    #[test]
+    fn optimize_is_idempotent() {
+        let optimized = optimize(MESSY);
+        assert_eq!(optimize(&optimized), optimized);
+    }
+
+    fn parsed(file_name: &str) -> Option<(String, IconWeight)> {
+        parse_icon_name_and_weight(file_name)
+    }
+
+    #[test]
+    fn parses_every_weight_suffix() {
+        for weight in IconWeight::ALL {
+            let file_name = match weight {
+                IconWeight::Regular => "cube.svg".to_string(),
+                _ => format!("cube-{weight}.svg"),
+            };
+            assert_eq!(parsed(&file_name), Some(("cube".to_string(), weight)));
+        }
+    }
+
+    #[test]
+    fn parses_names_containing_weights() {
+        let regular = |name: &str| Some((name.to_string(), IconWeight::Regular));
+        assert_eq!(parsed("bold-arrow.svg"), regular("bold-arrow"));
+        assert_eq!(parsed("text-bolder.svg"), regular("text-bolder"));
+        assert_eq!(parsed("lightbulb.svg"), regular("lightbulb"));
+        assert_eq!(parsed("paint-bucket-fill.svg").unwrap().1, IconWeight::Fill);
+        assert_eq!(
+            parsed("text-bold-bold.svg"),
+            Some(("text-bold".to_string(), IconWeight::Bold))
+        );
+        assert_eq!(
+            parsed("thin-line-duotone.svg"),
+            Some(("thin-line".to_string(), IconWeight::Duotone))
+        );
+    }
+
+    #[test]
+    fn rejects_files_without_a_name() {
+        assert_eq!(parsed("cube.png"), None);
+        assert_eq!(parsed(".svg"), None);
+        assert_eq!(parsed("-bold.svg"), None);
+    }
+
+    #[test]
+    fn weight_directory_decides_the_weight() {
+        assert_eq!(
+            parse_icon_name_for_weight("cube-bold.svg", &IconWeight::Bold).as_deref(),
+            Some("cube")
+        );
+        // A regular icon whose name ends in a weight keeps its whole name.
+        assert_eq!(
+            parse_icon_name_for_weight("text-bold.svg", &IconWeight::Regular).as_deref(),
+            Some("text-bold")
+        );
+        assert_eq!(
+            parse_icon_name_for_weight("cube.svg", &IconWeight::Fill).as_deref(),
+            Some("cube")
+        );
+    }
+
+    #[test]
+    fn placeholder_is_a_valid_icon_noting_the_request() {
+        let src = placeholder(2884, &IconWeight::Duotone);
+        assert_eq!(validate(&src), Ok(()));
+        assert!(src.contains("<!-- Icon not found: 2884 - duotone -->"));
+        assert!(render_png(&src, 32).is_ok());
+    }
+}
@@ -0,0 +1,97 @@
+//! Search synonyms, which let queries match icons by words that appear in none of their names or
+//! tags.
+//!
+//! The synonym file is a JSON object mapping a search term to the terms it should also match:
+//!
+//! ```json
+//! {
+//!   "cart": ["shopping-cart", "basket"],
+//!   "delete": ["trash", "recycle"]
+//! }
+//! ```
+//!
+//! Terms are matched case-insensitively, against both the whole query and each of its words.
+//! Expansion is one-way: searching `cart` also matches `basket`, but not the reverse unless
+//! `basket` has its own entry.
+
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SynonymError {
+    #[error("Failed to read synonym file {path}: {source}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("Failed to parse synonym file {path}: {source}")]
+    Parse {
+        path: String,
+        source: serde_json::Error,
+    },
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Synonyms(HashMap<String, Vec<String>>);
+
+impl Synonyms {
+    /// Load the synonym file at `path`. A missing file is treated as empty, so that the server
+    /// runs without one.
+    pub async fn load(path: &str) -> Result<Self, SynonymError> {
+        let contents = match tokio::fs::read_to_string(path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                tracing::warn!("No synonym file at {path}, searching without synonyms");
+                return Ok(Self::default());
+            }
+            Err(source) => {
+                return Err(SynonymError::Read {
+                    path: path.to_string(),
+                    source,
+                })
+            }
+        };
+        Self::parse(&contents).map_err(|source| SynonymError::Parse {
+            path: path.to_string(),
+            source,
+        })
+    }
+
+    pub fn parse(json: &str) -> Result<Self, serde_json::Error> {
+        let map = serde_json::from_str::<HashMap<String, Vec<String>>>(json)?;
+        Ok(Self(
+            map.into_iter()
+                .map(|(term, synonyms)| {
+                    let synonyms = synonyms
+                        .into_iter()
+                        .map(|s| s.trim().to_lowercase())
+                        .filter(|s| !s.is_empty())
+                        .collect();
+                    (term.trim().to_lowercase(), synonyms)
+                })
+                .collect(),
+        ))
+    }
+
+    /// The number of terms with synonyms.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The additional terms to search for alongside `q`, without duplicates or `q` itself.
+    pub fn expand(&self, q: &str) -> Vec<String> {
+        let q = q.trim().to_lowercase();
+        let mut terms = Vec::<String>::new();
+        let words = std::iter::once(q.as_str()).chain(q.split_whitespace());
+        for synonym in words.filter_map(|word| self.0.get(word)).flatten() {
+            if *synonym != q && !terms.contains(synonym) {
+                terms.push(synonym.clone());
+            }
+        }
+        terms
+    }
+}
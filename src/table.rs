@@ -1,12 +1,10 @@
+use crate::config::AppSheetConfig;
 use crate::icons::{Category, FigmaCategory, IconStatus};
 use serde::Deserialize;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::str::FromStr;
 use thiserror::Error;
 
-const APPSHEET_REGION: &str = "www.appsheet.com";
-const APP_ID: &str = "14ed274a-6160-4aae-8ee2-9f746dc77f64";
-const TABLE_NAME: &str = "Icon Inventory";
-
 #[derive(Clone, Debug, Default, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct TableIcon {
@@ -127,7 +125,7 @@ where
     let categories: Vec<&str> = re.split(&categories).collect();
     let mut result = Vec::new();
     for category in categories {
-        match Category::from_str(&category) {
+        match Category::from_str(category) {
             Ok(cat) => result.push(cat),
             Err(_) => {
                 tracing::warn!("Unknown category: {category}");
@@ -150,28 +148,100 @@ impl std::fmt::Debug for TableClient {
 pub enum TableClientError {
     #[error("Missing GOOGLE_APPSHEET_APPLICATION_KEY")]
     MissingKey,
-    #[error("Failed to perform Google API request")]
-    BadRequest,
-    #[error("Failed to parse response: {0}")]
-    ParseError(String),
+    #[error("Failed to perform Google API request: {0}")]
+    BadRequest(#[from] reqwest::Error),
+    #[error("AppSheet rejected the application key ({status}): {body}")]
+    Unauthorized { status: u16, body: String },
+    #[error("AppSheet server error ({0}): {1}")]
+    ServerError(u16, String),
+    #[error("Unexpected AppSheet response ({0}): {1}")]
+    UnexpectedStatus(u16, String),
+    #[error("Failed to parse response: {error}; body: {body}")]
+    ParseError { error: String, body: String },
+}
+
+/// Response bodies are attached to errors for debugging, but AppSheet error pages and full
+/// table dumps can be large, so only a prefix is kept.
+const ERROR_BODY_LIMIT: usize = 512;
+
+fn truncate_body(body: &[u8]) -> String {
+    let body = String::from_utf8_lossy(body);
+    match body.char_indices().nth(ERROR_BODY_LIMIT) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.into_owned(),
+    }
+}
+
+/// Identifies a particular revision of the AppSheet table, so that unchanged tables can be
+/// skipped on subsequent syncs. AppSheet does not reliably send an `ETag`, so a hash of the
+/// response body is kept as a fallback.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TableVersion {
+    pub etag: Option<String>,
+    pub hash: u64,
 }
 
 impl TableClient {
-    pub fn base_url() -> String {
-        format!("https://{APPSHEET_REGION}/api/v2/apps/{APP_ID}/tables/{TABLE_NAME}/Action")
+    /// Builds the AppSheet action URL for the configured table.
+    pub fn base_url(config: &AppSheetConfig) -> String {
+        format!(
+            "https://{}/api/v2/apps/{}/tables/{}/Action",
+            config.region, config.app_id, config.table_name
+        )
     }
 
-    pub async fn sync() -> Result<Vec<TableIcon>, TableClientError> {
+    pub async fn sync(config: &AppSheetConfig) -> Result<Vec<TableIcon>, TableClientError> {
+        TableClient::sync_if_changed(config, None)
+            .await
+            .map(|res| res.map(|(icons, _)| icons).unwrap_or_default())
+    }
+
+    /// Like [`TableClient::sync_if_changed`], but only returns icons last updated in or after the
+    /// `since` version, along with how many were left out. AppSheet's Find action can't filter,
+    /// so the whole table is still fetched. Icons without a `Last Updated` version are always
+    /// returned.
+    pub async fn sync_since(
+        config: &AppSheetConfig,
+        previous: Option<&TableVersion>,
+        since: f64,
+    ) -> Result<Option<(Vec<TableIcon>, usize, TableVersion)>, TableClientError> {
+        let Some((icons, version)) = TableClient::sync_if_changed(config, previous).await? else {
+            return Ok(None);
+        };
+        let total = icons.len();
+        // Several icons share each version, so ones updated in `since` itself may still be new.
+        let icons = icons
+            .into_iter()
+            .filter(|icon| icon.last_updated_at.is_none_or(|updated| updated >= since))
+            .collect::<Vec<_>>();
+        let skipped = total - icons.len();
+        Ok(Some((icons, skipped, version)))
+    }
+
+    /// Fetch the table, returning `None` if it is unchanged since the `previous` version. When
+    /// no previous version is given, the table is always returned.
+    pub async fn sync_if_changed(
+        config: &AppSheetConfig,
+        previous: Option<&TableVersion>,
+    ) -> Result<Option<(Vec<TableIcon>, TableVersion)>, TableClientError> {
         let client = reqwest::Client::new();
-        let url = TableClient::base_url();
-        let access_key = std::env::var("GOOGLE_APPSHEET_APPLICATION_KEY")
-            .map_err(|_| TableClientError::MissingKey)?;
+        let url = TableClient::base_url(config);
+        let access_key = config
+            .application_key
+            .as_ref()
+            .ok_or(TableClientError::MissingKey)?
+            .expose();
 
-        let response = client
+        let mut request = client
             .post(&url)
             .header("Content-Type", "application/json")
             .header("Accept", "application/json")
-            .header("ApplicationAccessKey", access_key)
+            .header("ApplicationAccessKey", access_key);
+        if let Some(etag) = previous.and_then(|v| v.etag.as_deref()) {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+
+        let response = request
             .json(&serde_json::json!({
                 "Action": "Find",
                 "Properties": {
@@ -179,12 +249,94 @@ impl TableClient {
                 }
             }))
             .send()
-            .await
-            .map_err(|_| TableClientError::BadRequest)?;
+            .await?;
 
-        response
-            .json()
-            .await
-            .map_err(|_| TableClientError::ParseError("Failed to parse JSON".to_string()))
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|h| h.to_str().ok())
+            .map(str::to_owned);
+        let body = response.bytes().await?;
+
+        if !status.is_success() {
+            let snippet = truncate_body(&body);
+            return Err(match status {
+                reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+                    TableClientError::Unauthorized {
+                        status: status.as_u16(),
+                        body: snippet,
+                    }
+                }
+                _ if status.is_server_error() => {
+                    TableClientError::ServerError(status.as_u16(), snippet)
+                }
+                _ => TableClientError::UnexpectedStatus(status.as_u16(), snippet),
+            });
+        }
+
+        let mut hasher = DefaultHasher::new();
+        body.hash(&mut hasher);
+        let version = TableVersion {
+            etag,
+            hash: hasher.finish(),
+        };
+        if previous.is_some_and(|prev| prev.hash == version.hash) {
+            return Ok(None);
+        }
+
+        let icons = serde_json::from_slice(&body).map_err(|e| TableClientError::ParseError {
+            error: e.to_string(),
+            body: truncate_body(&body),
+        })?;
+        Ok(Some((icons, version)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icons::Icon;
+
+    fn row(notes: &str) -> TableIcon {
+        serde_json::from_value(serde_json::json!({
+            "Row ID": "rid-1",
+            "Name": "cube",
+            "Alias": "",
+            "Codepoint": "57344",
+            "Status": "Implemented",
+            "Search Categories": "Design, Objects",
+            "Category": "Design",
+            "Tags": "box, shape",
+            "Notes": notes,
+            "Release": "1.0",
+            "Last Updated": "",
+            "Deprecated": "",
+            "Published": "Y",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn empty_notes_are_none() {
+        assert_eq!(row("").notes, None);
+        assert_eq!(
+            row("Pairs with sphere").notes.as_deref(),
+            Some("Pairs with sphere")
+        );
+    }
+
+    #[test]
+    fn notes_are_serialized_even_when_absent() {
+        let json = serde_json::to_value(Icon::from(row(""))).unwrap();
+        assert_eq!(json["notes"], serde_json::Value::Null);
+        assert!(json.as_object().unwrap().contains_key("notes"));
+
+        let json = serde_json::to_value(Icon::from(row("Pairs with sphere"))).unwrap();
+        assert_eq!(json["notes"], "Pairs with sphere");
     }
 }
@@ -1,3 +1,4 @@
 #!/bin/sh
 cargo build --release
+./target/release/phosphor-server migrate
 ./target/release/phosphor-server
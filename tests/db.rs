@@ -0,0 +1,648 @@
+//! Database tests, run against the Postgres database at `PHOSPHOR_TEST_DATABASE_URL`. The
+//! database is migrated and emptied before each test, so it must be one set aside for testing.
+//! The tests are skipped when the variable is unset.
+
+use migration::{Migrator, MigratorTrait};
+use phosphor_server::{
+    config::PageSize,
+    db::{
+        Db, IconQuery, IconReleaseQuery, IconSearch, OrderColumn, OrderDirection, OrderField,
+        ALIAS_WEIGHT, MAX_SEARCH_TERM_LENGTH, TAG_WEIGHT,
+    },
+    entities::{icons, svgs},
+    icons::{Category, IconWeight},
+};
+use sea_orm::{ConnectionTrait, Database, EntityTrait};
+use tokio::sync::{Mutex, MutexGuard};
+
+/// Every test empties the same tables, so they take turns.
+static DATABASE: Mutex<()> = Mutex::const_new(());
+
+struct TestDb {
+    db: Db,
+    _guard: MutexGuard<'static, ()>,
+}
+
+async fn setup() -> Option<TestDb> {
+    let Ok(url) = std::env::var("PHOSPHOR_TEST_DATABASE_URL") else {
+        eprintln!("PHOSPHOR_TEST_DATABASE_URL is not set, skipping");
+        return None;
+    };
+    let guard = DATABASE.lock().await;
+    let conn = Database::connect(&url)
+        .await
+        .expect("Failed to connect to test database");
+    Migrator::up(&conn, None)
+        .await
+        .expect("Failed to migrate test database");
+    conn.execute_unprepared("TRUNCATE icons, svgs RESTART IDENTITY CASCADE")
+        .await
+        .expect("Failed to empty test database");
+
+    Some(TestDb {
+        db: Db {
+            conn,
+            current_version: None,
+            search_threshold: 0.2,
+            search_min_threshold: 0.0,
+            search_max_distance: 2,
+            page_size: PageSize::default(),
+        },
+        _guard: guard,
+    })
+}
+
+/// A published icon with nothing but a name, to be customized with struct update syntax.
+fn icon(id: i32, name: &str) -> icons::Model {
+    icons::Model {
+        id,
+        rid: format!("rid-{id}"),
+        name: name.to_string(),
+        status: "Implemented".to_string(),
+        category: "Design".to_string(),
+        search_categories: vec!["Design".to_string()],
+        tags: vec![],
+        notes: None,
+        released_at: Some(1.0),
+        last_updated_at: Some(1.0),
+        deprecated_at: None,
+        published: true,
+        alias: None,
+        code: None,
+    }
+}
+
+fn svg(icon_id: i32, weight: IconWeight) -> svgs::Model {
+    svgs::Model {
+        id: 0,
+        icon_id,
+        src: format!(r#"<svg viewBox="0 0 256 256"><!-- {icon_id} {weight} --></svg>"#),
+        weight: weight.to_string(),
+    }
+}
+
+async fn insert(db: &Db, icons: Vec<icons::Model>, svgs: Vec<svgs::Model>) {
+    icons::Entity::insert_many(icons.into_iter().map(icons::ActiveModel::from))
+        .exec_without_returning(&db.conn)
+        .await
+        .expect("Failed to insert icons");
+    db.upsert_svgs(svgs).await.expect("Failed to insert SVGs");
+}
+
+#[tokio::test]
+async fn stats_summarize_weight_coverage() {
+    let Some(test) = setup().await else { return };
+    let db = &test.db;
+    let mut svgs = IconWeight::ALL
+        .into_iter()
+        .map(|weight| svg(1, weight))
+        .collect::<Vec<_>>();
+    svgs.push(svg(2, IconWeight::Regular));
+    svgs.push(svg(2, IconWeight::Bold));
+    insert(
+        db,
+        vec![
+            icon(1, "cube"),
+            icon(2, "sphere"),
+            icon(3, "cone"),
+            icons::Model {
+                published: false,
+                ..icon(4, "prism")
+            },
+        ],
+        svgs,
+    )
+    .await;
+
+    let weights = db.dump_stats().await.unwrap().weights;
+    assert_eq!(weights.complete, 1);
+    assert_eq!(weights.missing["regular"], 1);
+    assert_eq!(weights.missing["bold"], 1);
+    assert_eq!(weights.missing["thin"], 2);
+    assert_eq!(weights.missing["duotone"], 2);
+    assert_eq!(weights.missing.len(), IconWeight::COUNT);
+}
+
+fn names(models: &[icons::Model]) -> Vec<&str> {
+    models.iter().map(|model| model.name.as_str()).collect()
+}
+
+#[tokio::test]
+async fn release_sort_puts_unreleased_icons_last() {
+    let Some(test) = setup().await else { return };
+    let db = &test.db;
+    insert(
+        db,
+        vec![
+            icons::Model {
+                released_at: Some(1.0),
+                ..icon(1, "old")
+            },
+            icons::Model {
+                released_at: None,
+                ..icon(2, "unreleased")
+            },
+            icons::Model {
+                released_at: Some(2.1),
+                ..icon(3, "new")
+            },
+        ],
+        vec![],
+    )
+    .await;
+
+    let by_release = |dir| IconQuery {
+        order: Some(vec![OrderField {
+            column: OrderColumn::Release,
+            dir: Some(dir),
+        }]),
+        ..IconQuery::new()
+    };
+    let newest = db
+        .get_icons(&by_release(OrderDirection::Desc))
+        .await
+        .unwrap();
+    assert_eq!(names(&newest), ["new", "old", "unreleased"]);
+    let oldest = db
+        .get_icons(&by_release(OrderDirection::Asc))
+        .await
+        .unwrap();
+    assert_eq!(names(&oldest), ["old", "new", "unreleased"]);
+
+    // Paging one icon at a time crosses into the NULLs without skipping or repeating.
+    let mut query = IconQuery {
+        limit: Some(1),
+        ..by_release(OrderDirection::Desc)
+    };
+    let mut paged = vec![];
+    loop {
+        let (page, cursor) = db.get_icon_page(&query).await.unwrap();
+        paged.extend(page);
+        match cursor {
+            Some(cursor) => query.cursor = Some(cursor),
+            None => break,
+        }
+    }
+    assert_eq!(names(&paged), ["new", "old", "unreleased"]);
+}
+
+#[tokio::test]
+async fn icons_are_found_by_alias() {
+    let Some(test) = setup().await else { return };
+    let db = &test.db;
+    insert(
+        db,
+        vec![
+            icons::Model {
+                alias: Some("instagram-logo".to_string()),
+                ..icon(1, "instagram")
+            },
+            icon(2, "cube"),
+        ],
+        vec![],
+    )
+    .await;
+
+    let found = db.get_icon_by_alias("instagram-logo").await.unwrap();
+    assert_eq!(found.map(|model| model.name).as_deref(), Some("instagram"));
+    assert_eq!(db.get_icon_by_alias("instagram").await.unwrap(), None);
+    assert_eq!(db.get_icon_by_alias("cube").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn exclusions_drop_icons_matching_an_included_filter() {
+    let Some(test) = setup().await else { return };
+    let db = &test.db;
+    insert(
+        db,
+        vec![
+            icons::Model {
+                search_categories: vec!["System".to_string()],
+                tags: vec!["gear".to_string()],
+                ..icon(1, "gear")
+            },
+            icons::Model {
+                search_categories: vec!["System".to_string(), "Brand".to_string()],
+                tags: vec!["gear".to_string(), "logo".to_string()],
+                ..icon(2, "gear-logo")
+            },
+            icons::Model {
+                search_categories: vec!["Brand".to_string()],
+                tags: vec!["logo".to_string()],
+                ..icon(3, "logo")
+            },
+        ],
+        vec![],
+    )
+    .await;
+
+    let query = IconQuery {
+        tags: Some(vec!["gear".to_string()]),
+        exclude_tags: Some(vec!["logo".to_string()]),
+        ..IconQuery::new()
+    };
+    assert_eq!(names(&db.get_icons(&query).await.unwrap()), ["gear"]);
+
+    let query = IconQuery {
+        category: Some(vec![Category::System]),
+        exclude_category: Some(vec![Category::Brand]),
+        ..IconQuery::new()
+    };
+    assert_eq!(names(&db.get_icons(&query).await.unwrap()), ["gear"]);
+
+    let query = IconQuery {
+        exclude_category: Some(vec![Category::System]),
+        ..IconQuery::new()
+    };
+    assert_eq!(names(&db.get_icons(&query).await.unwrap()), ["logo"]);
+}
+
+#[tokio::test]
+async fn later_order_fields_break_ties() {
+    let Some(test) = setup().await else { return };
+    let db = &test.db;
+    let designed = |id, name| icons::Model {
+        status: "Designed".to_string(),
+        ..icon(id, name)
+    };
+    insert(
+        db,
+        vec![
+            icon(1, "beta"),
+            designed(2, "delta"),
+            icon(3, "alpha"),
+            designed(4, "gamma"),
+            icon(5, "epsilon"),
+        ],
+        vec![],
+    )
+    .await;
+
+    let status_then_name = || IconQuery {
+        order: Some(vec![
+            OrderField {
+                column: OrderColumn::Status,
+                dir: None,
+            },
+            OrderField {
+                column: OrderColumn::Name,
+                dir: Some(OrderDirection::Desc),
+            },
+        ]),
+        ..IconQuery::new()
+    };
+    assert_eq!(
+        names(&db.get_icons(&status_then_name()).await.unwrap()),
+        ["gamma", "delta", "epsilon", "beta", "alpha"]
+    );
+
+    // The secondary order holds across pages.
+    let mut paged = vec![];
+    for offset in 0..3 {
+        let page = IconQuery {
+            limit: Some(2),
+            offset: Some(offset * 2),
+            ..status_then_name()
+        };
+        paged.extend(db.get_icons(&page).await.unwrap());
+    }
+    assert_eq!(
+        names(&paged),
+        ["gamma", "delta", "epsilon", "beta", "alpha"]
+    );
+}
+
+#[tokio::test]
+async fn batched_svg_upserts_update_on_conflict() {
+    let Some(test) = setup().await else { return };
+    let db = &test.db;
+    insert(
+        db,
+        vec![icon(1, "cube"), icon(2, "sphere")],
+        vec![svg(1, IconWeight::Regular), svg(1, IconWeight::Bold)],
+    )
+    .await;
+
+    let replaced = svgs::Model {
+        src: "<svg>replaced</svg>".to_string(),
+        ..svg(1, IconWeight::Bold)
+    };
+    let rows = db
+        .upsert_svgs(vec![
+            svgs::Model {
+                src: "<svg>superseded</svg>".to_string(),
+                ..svg(1, IconWeight::Bold)
+            },
+            replaced.clone(),
+            svg(2, IconWeight::Regular),
+        ])
+        .await
+        .unwrap();
+    // The superseded duplicate is dropped before the statement runs.
+    assert_eq!(rows, 2);
+    assert_eq!(db.upsert_svgs(vec![]).await.unwrap(), 0);
+
+    let mut stored = svgs::Entity::find()
+        .all(&db.conn)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|svg| (svg.icon_id, svg.weight, svg.src))
+        .collect::<Vec<_>>();
+    stored.sort();
+    assert_eq!(
+        stored,
+        [
+            (1, "bold".to_string(), replaced.src),
+            (1, "regular".to_string(), svg(1, IconWeight::Regular).src),
+            (2, "regular".to_string(), svg(2, IconWeight::Regular).src),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn failed_svg_batches_store_nothing() {
+    let Some(test) = setup().await else { return };
+    let db = &test.db;
+    insert(db, vec![icon(1, "cube")], vec![]).await;
+
+    // The second SVG references a missing icon, so the whole batch is rolled back.
+    let res = db
+        .upsert_svgs(vec![
+            svg(1, IconWeight::Regular),
+            svg(99, IconWeight::Regular),
+        ])
+        .await;
+    assert!(res.is_err());
+    assert!(svgs::Entity::find().all(&db.conn).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn name_matches_outrank_alias_and_tag_matches() {
+    let Some(test) = setup().await else { return };
+    let db = &test.db;
+    insert(
+        db,
+        vec![
+            icons::Model {
+                tags: vec!["anchor".to_string()],
+                ..icon(1, "boat")
+            },
+            icons::Model {
+                alias: Some("anchor".to_string()),
+                ..icon(2, "anchor-simple")
+            },
+            icon(3, "anchor"),
+            icon(4, "cube"),
+        ],
+        vec![],
+    )
+    .await;
+
+    let search = IconSearch {
+        q: "anchor".to_string(),
+        ..IconSearch::default()
+    };
+    let (results, total) = db.query_icons(&search, &[]).await.unwrap();
+    let ranked = results
+        .iter()
+        .map(|result| (result.icon.name.as_str(), result.score))
+        .collect::<Vec<_>>();
+    assert_eq!(total, 3);
+    assert_eq!(ranked.len(), 3);
+    assert_eq!(ranked[0], ("anchor", 1.0));
+    assert_eq!(ranked[1], ("anchor-simple", ALIAS_WEIGHT));
+    assert_eq!(ranked[2], ("boat", TAG_WEIGHT));
+}
+
+#[tokio::test]
+async fn search_pages_preserve_relevance_order() {
+    let Some(test) = setup().await else { return };
+    let db = &test.db;
+    insert(
+        db,
+        vec![
+            icons::Model {
+                tags: vec!["arrow".to_string()],
+                ..icon(1, "compass")
+            },
+            icon(2, "arrow-up"),
+            icon(3, "arrow"),
+            icon(4, "arrow-down"),
+            icons::Model {
+                alias: Some("arrow".to_string()),
+                ..icon(5, "caret")
+            },
+            icon(6, "cube"),
+        ],
+        vec![],
+    )
+    .await;
+
+    let search = |limit, offset| IconSearch {
+        q: "arrow".to_string(),
+        limit: Some(limit),
+        offset: Some(offset),
+        ..IconSearch::default()
+    };
+    let (all, total) = db.query_icons(&search(10, 0), &[]).await.unwrap();
+    assert_eq!(total, 5);
+    let all = all
+        .into_iter()
+        .map(|result| result.icon.name)
+        .collect::<Vec<_>>();
+    assert_eq!(all[0], "arrow");
+
+    let mut paged = vec![];
+    for offset in (0..6).step_by(2) {
+        let (page, page_total) = db.query_icons(&search(2, offset), &[]).await.unwrap();
+        assert_eq!(page_total, total);
+        assert!(page.len() <= 2);
+        paged.extend(page.into_iter().map(|result| result.icon.name));
+    }
+    assert_eq!(paged, all);
+
+    // Pages default to the configured size, and larger limits are capped.
+    let mut db = test.db;
+    db.page_size = PageSize { default: 2, max: 3 };
+    let default = IconSearch {
+        q: "arrow".to_string(),
+        ..IconSearch::default()
+    };
+    let (page, _) = db.query_icons(&default, &[]).await.unwrap();
+    assert_eq!(page.len(), 2);
+    let (page, page_total) = db.query_icons(&search(10, 0), &[]).await.unwrap();
+    assert_eq!((page.len(), page_total), (3, total));
+}
+
+#[tokio::test]
+async fn icons_are_filtered_by_notes() {
+    let Some(test) = setup().await else { return };
+    let db = &test.db;
+    insert(
+        db,
+        vec![
+            icons::Model {
+                notes: Some("Pairs with sphere".to_string()),
+                ..icon(1, "cube")
+            },
+            icon(2, "sphere"),
+            icons::Model {
+                notes: Some("Drawn on a 16px grid".to_string()),
+                ..icon(3, "cone")
+            },
+        ],
+        vec![],
+    )
+    .await;
+
+    let with_notes = |has_notes| IconQuery {
+        has_notes,
+        ..IconQuery::new()
+    };
+    let noted = db.get_icons(&with_notes(Some(true))).await.unwrap();
+    assert_eq!(names(&noted), ["cone", "cube"]);
+    let unnoted = db.get_icons(&with_notes(Some(false))).await.unwrap();
+    assert_eq!(names(&unnoted), ["sphere"]);
+    let all = db.get_icons(&with_notes(None)).await.unwrap();
+    assert_eq!(names(&all), ["cone", "cube", "sphere"]);
+}
+
+#[tokio::test]
+async fn overlong_searches_match_nothing() {
+    let Some(test) = setup().await else { return };
+    let db = &test.db;
+    insert(db, vec![icon(1, "arrow")], vec![]).await;
+
+    // Up to the limit, long terms reach the typo-tolerant scoring without failing, and past it
+    // they match nothing rather than erroring.
+    let search = |q: String| IconSearch {
+        q,
+        ..IconSearch::default()
+    };
+    let (results, total) = db
+        .query_icons(&search("arrow".repeat(MAX_SEARCH_TERM_LENGTH / 5)), &[])
+        .await
+        .unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(results[0].icon.name, "arrow");
+    let (results, total) = db.query_icons(&search("a".repeat(300)), &[]).await.unwrap();
+    assert_eq!((results.len(), total), (0, 0));
+}
+
+#[tokio::test]
+async fn bulk_queries_export_past_the_page_size() {
+    let Some(test) = setup().await else { return };
+    let db = &test.db;
+    insert(
+        db,
+        vec![icon(1, "cone"), icon(2, "cube"), icon(3, "sphere")],
+        vec![],
+    )
+    .await;
+
+    let page_size = PageSize { default: 1, max: 2 };
+    let query = IconQuery::new().bulk(&page_size);
+    assert_eq!(
+        names(&db.get_icons(&query).await.unwrap()),
+        ["cone", "cube", "sphere"]
+    );
+
+    let query = IconQuery {
+        limit: Some(10),
+        ..IconQuery::new()
+    }
+    .bulk(&page_size);
+    assert_eq!(
+        names(&db.get_icons(&query).await.unwrap()),
+        ["cone", "cube"]
+    );
+    assert_eq!(db.count_icons(&query).await.unwrap(), 3);
+}
+
+#[tokio::test]
+async fn current_version_hides_later_releases() {
+    let Some(test) = setup().await else { return };
+    insert(
+        &test.db,
+        vec![
+            icons::Model {
+                released_at: Some(2.0),
+                last_updated_at: Some(2.2),
+                ..icon(1, "cone")
+            },
+            icons::Model {
+                released_at: Some(2.1),
+                last_updated_at: Some(2.1),
+                ..icon(2, "cube")
+            },
+            icons::Model {
+                released_at: Some(2.2),
+                last_updated_at: Some(2.2),
+                ..icon(3, "sphere")
+            },
+            icons::Model {
+                released_at: None,
+                last_updated_at: None,
+                ..icon(4, "torus")
+            },
+        ],
+        vec![],
+    )
+    .await;
+
+    let mut db = test.db;
+    db.current_version = Some(2.1);
+    let info = db.get_library_info().await.unwrap();
+    assert_eq!((info.count, info.version), (3, 2.1));
+    assert_eq!(
+        names(&db.get_latest_icons(10).await.unwrap()),
+        ["cube", "cone"]
+    );
+
+    let updated = IconQuery::new()
+        .updated(db.until_current_version(IconReleaseQuery::between("2.0", "2.2").unwrap()));
+    assert_eq!(names(&db.get_icons(&updated).await.unwrap()), ["cube"]);
+}
+
+#[tokio::test]
+async fn wildcard_names_keep_the_other_filters() {
+    let Some(test) = setup().await else { return };
+    let db = &test.db;
+    insert(
+        db,
+        vec![
+            icons::Model {
+                notes: Some("Rounded".to_string()),
+                ..icon(1, "cone")
+            },
+            icon(2, "cube"),
+            icons::Model {
+                published: false,
+                ..icon(3, "sphere")
+            },
+        ],
+        vec![],
+    )
+    .await;
+
+    let query = IconQuery {
+        name: Some("*".to_string()),
+        has_notes: Some(false),
+        ..IconQuery::new()
+    };
+    assert_eq!(names(&db.get_icons(&query).await.unwrap()), ["cube"]);
+    assert_eq!(db.count_icons(&query).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn suggestions_respect_the_minimum_threshold() {
+    let Some(test) = setup().await else { return };
+    insert(&test.db, vec![icon(1, "cube")], vec![]).await;
+
+    let mut db = test.db;
+    db.search_threshold = 0.0;
+    assert_eq!(db.suggest_names("cubes", 5).await.unwrap(), ["cube"]);
+    db.search_min_threshold = 0.99;
+    assert!(db.suggest_names("cubes", 5).await.unwrap().is_empty());
+}